@@ -0,0 +1,135 @@
+// Benchmark of the single pass statistics engine against the previous
+// approach of iterating the node store once per statistic, over the four
+// statistics computed before the engine, and of the parallel engine against
+// the single pass one over all the built-in statistics. It runs over synthetic
+// ledgers of increasing size built by the generator module.
+//
+// cargo bench --bench statistics
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust_challenge::generator::{self, GeneratorOptions};
+use rust_challenge::graph::Graph;
+use rust_challenge::services::statistics::{self, Accumulated, TIMESTAMP_RANGE};
+
+const SIZES: [u32; 3] = [10_000, 100_000, 1_000_000];
+const ROUNDS: u32 = 5;
+
+// Synthetic ledger of `size` transactions with uniform tip selection, 4
+// arrivals per time unit and a delay of 5 time units.
+fn synthetic_ledger(size: u32) -> Graph {
+    generator::generate(&GeneratorOptions {
+        transactions: size,
+        lambda: 4.0,
+        delay: 5.0,
+        ..GeneratorOptions::default()
+    })
+    .expect("synthetic ledger is valid")
+}
+
+// The statistics as they were computed before the single pass engine, one
+// traversal of the node store per statistic.
+mod multi_pass {
+    use super::*;
+
+    fn average_depth(graph: &Graph) -> f64 {
+        graph
+            .nodes
+            .values()
+            .map(|node| node.metrics.depth)
+            .sum::<u32>() as f64
+            / graph.num_nodes as f64
+    }
+    fn average_nodes_by_depth(graph: &Graph) -> f64 {
+        let mut score_depth = HashMap::new();
+        for node in graph.nodes.values().filter(|v| v.id != 1) {
+            let entry = score_depth.entry(node.metrics.depth).or_insert(0);
+            *entry += 1;
+        }
+        let num_scores = score_depth.len() as f64;
+        let depths = score_depth.into_values().sum::<u32>() as f64;
+        depths / num_scores
+    }
+    fn average_in_references(graph: &Graph) -> f64 {
+        graph
+            .nodes
+            .values()
+            .map(|node| node.metrics.in_reference)
+            .sum::<u32>() as f64
+            / graph.num_nodes as f64
+    }
+    fn range_timestamps(graph: &Graph) -> Vec<(u32, u64)> {
+        let mut range_timestamps: HashMap<u32, u64> = HashMap::new();
+        for node in graph.nodes.values() {
+            let range = node.timestamp / TIMESTAMP_RANGE;
+            let entry = range_timestamps.entry(range).or_insert(0);
+            *entry += 1;
+        }
+        let mut items = range_timestamps
+            .iter()
+            .map(|(&a, &b)| (a, b))
+            .collect::<Vec<(u32, u64)>>();
+        items.sort_by_key(|&k| k);
+        items
+    }
+
+    pub fn stats(graph: &Graph) -> impl Sized {
+        (
+            average_depth(graph),
+            average_nodes_by_depth(graph),
+            average_in_references(graph),
+            range_timestamps(graph),
+        )
+    }
+}
+
+// Accumulators of the same four statistics in the single pass engine.
+const BASELINE: Accumulated = Accumulated {
+    depths: true,
+    in_references: true,
+    parents: false,
+    generations: false,
+    timestamps: true,
+    open_tips: false,
+    antichains: false,
+};
+
+fn measure<T>(mut run: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(run());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+    println!(
+        "{:>10} {:>14} {:>14} {:>8} {:>14} {:>14} {:>8}",
+        "nodes",
+        "multi pass",
+        "single pass",
+        "speedup",
+        "all stats",
+        format!("{:} threads", threads),
+        "speedup"
+    );
+    for size in SIZES {
+        let graph = synthetic_ledger(size);
+        let multi = measure(|| multi_pass::stats(&graph));
+        let single = measure(|| statistics::stats_with(&graph, BASELINE, 1));
+        let all = measure(|| statistics::stats(&graph));
+        let parallel = measure(|| statistics::stats_parallel(&graph, threads));
+        println!(
+            "{:>10} {:>14.2?} {:>14.2?} {:>7.2}x {:>14.2?} {:>14.2?} {:>7.2}x",
+            size,
+            multi,
+            single,
+            multi.as_secs_f64() / single.as_secs_f64(),
+            all,
+            parallel,
+            all.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,1144 @@
+// CLI module. It parses the command line in subcommands with their options
+// and classifies the failures, each class with its own exit code.
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use rust_challenge::domain::ParentPolicy;
+use rust_challenge::export::dot::DotOptions;
+use rust_challenge::export::ExportError;
+use rust_challenge::generator::{GeneratorError, GeneratorOptions};
+use rust_challenge::infra::InfraError;
+use rust_challenge::output::Format;
+use rust_challenge::query::Expr;
+use rust_challenge::services::confirmation::{ConfirmationError, Rule};
+use rust_challenge::services::conflicts::ConflictError;
+use rust_challenge::services::inspection::InspectionError;
+use rust_challenge::services::liveness::Thresholds;
+use rust_challenge::services::milestones::MilestoneError;
+use rust_challenge::services::statistics::StatisticsError;
+use rust_challenge::simulation::{SimulationError, SimulationOptions, Start, Strategy};
+use rust_challenge::terminal::TerminalError;
+
+use thiserror::Error;
+
+/// Subcommands, a command line without one runs `stats`.
+const COMMANDS: [&str; 19] = [
+    "stats",
+    "validate",
+    "convert",
+    "export",
+    "query",
+    "inspect",
+    "path",
+    "lca",
+    "confirm",
+    "milestones",
+    "conflicts",
+    "diff",
+    "report",
+    "dag",
+    "simulate",
+    "generate",
+    "repl",
+    "serve",
+    "help",
+];
+
+pub const USAGE: &str = "Ledger statistics
+
+Usage: rust-challenge [COMMAND] [OPTIONS] <ARGS>
+
+Commands:
+  stats <db>               Print the statistics of a ledger (default command)
+  validate <db>            Check that a ledger can be loaded
+  convert <input> <output> Convert a ledger between formats
+  export <db>              Export the DAG of a ledger
+  query <db>               List transactions of a ledger
+  inspect <db> <id>        Print the details of a transaction
+  path <db> <from> <to>    Print the paths between two transactions
+  lca <db> <id> <id>       Print the lowest common ancestors of two transactions
+  confirm <db>             Label the transactions confirmed under a rule
+  milestones <db>          Print the transactions confirmed by every milestone
+  conflicts <db> <sets>    Resolve conflicting transactions by cumulative weight
+  diff <db> <db>           Compare the statistics of two ledgers
+  report <db>              Write an HTML report of a ledger
+  dag <db>                 Render the DAG of a ledger in the terminal
+  simulate <db>            Simulate tip selection random walks over a ledger
+  generate                 Generate a synthetic ledger
+  repl <db>                Interactive shell over a ledger loaded once
+  serve <db>               HTTP JSON API over a ledger loaded once
+  help [COMMAND]           Print this help or the help of a command
+
+Global options:
+  --parent-refs once|twice  How duplicated parents are counted as in references
+  --threads N               Workers computing the statistics (1 by default)
+  --ascii                   Avoid unicode characters in the terminal output
+  --max-age N               Age after the last approval of left behind transactions (50 by default)
+  --rule <rule>             Confirmation rule (`weight >= 10` by default), see `help confirm`
+  --confirmation-weight W   Same as `--rule \"weight >= W\"`
+  -h, --help                Print help
+
+Exit codes: 0 success, 1 output error, 2 usage error, 3 missing file,
+4 parse error, 5 graph error.
+";
+
+const STATS_USAGE: &str = "Usage: rust-challenge stats <db> [OPTIONS]
+
+Options:
+  --stats name,...                     Select statistics (all by default)
+  --list-stats                         List the statistic names
+  --format text|json|csv|prometheus    Output format (text by default)
+  --chart                              Add bar charts of the histograms to the text output
+";
+
+const VALIDATE_USAGE: &str = "Usage: rust-challenge validate <db>
+
+Loads the ledger and prints its number of transactions and tips.
+";
+
+const CONVERT_USAGE: &str = "Usage: rust-challenge convert <input> <output> [OPTIONS]
+
+The input is a database file, or GraphML when its extension is `.graphml`.
+The output is `-` for the standard output.
+
+Options:
+  --to database|graphml|gexf|dot   Output format (by the output extension by default)
+";
+
+const EXPORT_USAGE: &str = "Usage: rust-challenge export <db> [OPTIONS]
+
+Options:
+  --to dot|graphml|gexf|database   Output format (dot by default)
+  --output <file|->                Output file (standard output by default)
+  --dot-tips                       Color the tips
+  --dot-most-referenced            Color the most referenced transaction
+  --dot-cone <id>                  Color the past and future cones of a transaction
+";
+
+const QUERY_USAGE: &str = "Usage: rust-challenge query <db> <QUERY> [OPTIONS]
+
+Queries:
+  --tips                 Transactions without approvers
+  --approvers <id>       Transactions referencing a transaction
+  --past-cone <id>       Transactions referenced directly or indirectly by a transaction
+  --future-cone <id>     Transactions referencing directly or indirectly a transaction
+  --where <filter>       Transactions matching a filter expression
+
+Options:
+  --summary              Print the statistics of the selected transactions instead
+  --format text|json     Output format (text by default)
+
+Filters compare the fields id, timestamp, depth, generation, in_reference,
+left, right and approvers (number of approvers) with =, !=, <, <=, >, >= or
+`between a and b`, test the flags tip, root and same_parents, and combine
+them with and, or, not and parentheses, e.g.
+
+  rust-challenge query db.txt --where \"depth > 5 and in_reference = 0\"
+";
+
+const INSPECT_USAGE: &str = "Usage: rust-challenge inspect <db> <id> [OPTIONS]
+
+Prints the parents, timestamp, depth, in references, approvers, cumulative
+weight and cone sizes of a transaction, and whether it is a tip.
+
+Options:
+  --format text|json     Output format (text by default)
+";
+
+const PATH_USAGE: &str = "Usage: rust-challenge path <db> <from> <to> [OPTIONS]
+
+Prints the shortest path of references between two transactions, when one
+is in the past cone of the other, and the number of distinct paths.
+
+Options:
+  --all                  List the paths, left parents first
+  --limit N              Paths listed with `--all` (100 by default)
+  --format text|json     Output format (text by default)
+";
+
+const LCA_USAGE: &str = "Usage: rust-challenge lca <db> <id> <id> [OPTIONS]
+
+Prints the lowest common ancestors of two transactions, the common ancestors
+not in the past cone of another one, with their depth and timestamp.
+
+Options:
+  --format text|json     Output format (text by default)
+";
+
+const CONFIRM_USAGE: &str = "Usage: rust-challenge confirm <db> [OPTIONS]
+
+Labels every transaction as confirmed or not under the rule of the global
+`--rule` option, the one of the liveness statistics, and prints the
+confirmation rate, overall and by timestamp range. Rules combine with `and`
+and `or` the conditions `weight >= W` (cumulative weight) and
+`coverage >= P%` (share of the tips approving the transaction directly or
+indirectly), e.g.
+
+  rust-challenge confirm db.txt --rule \"weight >= 10 or coverage >= 66%\"
+
+Options:
+  --id <id>              Print the confirmation of a transaction
+  --list                 Print the confirmation of every transaction
+  --format text|json     Output format (text by default)
+";
+
+const MILESTONES_USAGE: &str = "Usage: rust-challenge milestones <db> <MILESTONES> [OPTIONS]
+
+Every transaction is confirmed by the first milestone, in timestamp order, whose
+past cone contains it. Prints the milestones with their intervals and the
+transactions they confirm, and the confirmation latencies (time between a
+transaction and its milestone).
+
+Milestones:
+  --file <file>          Milestone ids, separated by spaces, commas or lines
+  --every T              Latest transaction of every period of T time units
+  --where <filter>       Transactions matching a filter expression (see `help query`)
+
+Options:
+  --list                 Print the milestone of every transaction
+  --format text|json     Output format (text by default)
+";
+
+const CONFLICTS_USAGE: &str = "Usage: rust-challenge conflicts <db> <sets> [OPTIONS]
+
+The sets file has a group of mutually conflicting transaction ids (double
+spends) per line, separated by spaces or commas. The transaction of every
+set with the highest cumulative weight wins and the others are invalidated
+with their future cones. The sets are resolved by their lowest id and the
+transactions invalidated by a set take no part in the following ones.
+Prints the winners, the inconsistent transactions (with two conflicting
+ones in their past cones) and the invalidated ones.
+
+Options:
+  --format text|json     Output format (text by default)
+";
+
+const DIFF_USAGE: &str = "Usage: rust-challenge diff <db> <db> [OPTIONS]
+
+Options:
+  --stats name,...       Select statistics (all by default)
+  --format text|json     Output format (text by default)
+";
+
+const REPORT_USAGE: &str = "Usage: rust-challenge report <db> [OPTIONS]
+
+Options:
+  --output <file|->      Output file (report.html by default)
+";
+
+const DAG_USAGE: &str = "Usage: rust-challenge dag <db> [OPTIONS]
+
+Options:
+  --around <id>          Render only a window around a transaction
+  --radius N             References away from `--around` rendered (2 by default)
+";
+
+const SIMULATE_USAGE: &str = "Usage: rust-challenge simulate <db> [OPTIONS]
+
+Runs tip selection random walks from the root, each step to an approver of
+the current transaction, and reports how often every tip is selected, the
+orphan rate (share of tips never selected) and the walk lengths.
+
+Options:
+  --strategy uniform|mcmc  Approvers chosen uniformly or by cumulative weight (uniform by default)
+  --alpha A                MCMC bias to the heaviest approvers (0.5 by default)
+  --start-depth D          Start from a transaction at depth D instead of the root
+  --walks N                Number of walks (1000 by default)
+  --seed S                 Seed of the random generator (0 by default)
+  --format text|json       Output format (text by default)
+";
+
+const GENERATE_USAGE: &str = "Usage: rust-challenge generate [OPTIONS]
+
+Writes a synthetic ledger in the database format. Transactions arrive as a
+Poisson process, each one sees the ledger as it was a network delay before
+and selects its two parents among the tips it sees.
+
+Options:
+  --transactions N         Transactions after the root (1000 by default)
+  --lambda L               Arrivals per time unit (4 by default)
+  --delay H                Network delay in time units (1 by default)
+  --strategy uniform|mcmc  Tips chosen uniformly or with MCMC walks (uniform by default)
+  --alpha A                MCMC bias to the heaviest approvers (0.5 by default)
+  --seed S                 Seed of the random generator (0 by default)
+  --output <file|->        Output file (standard output by default)
+";
+
+const REPL_USAGE: &str = "Usage: rust-challenge repl <db>
+
+Loads the ledger once and reads commands (`help` lists them) until `exit`.
+In a terminal, the up and down arrows browse the history and tab completes
+the commands.
+";
+
+const SERVE_USAGE: &str = "Usage: rust-challenge serve <db> [OPTIONS]
+
+Endpoints:
+  GET  /stats                          Statistics
+  GET  /tips                           Tips
+  GET  /transactions/<id>              Details of a transaction
+  GET  /transactions/<id>/past-cone    Past cone of a transaction
+  GET  /transactions/<id>/future-cone  Future cone of a transaction
+  GET  /path?from=<id>&to=<id>         Shortest path and number of paths
+  POST /transactions                   New transaction {\"left\", \"right\", \"timestamp\"}
+  GET  /events                         Server-sent events: transaction, rejected and stats
+
+Options:
+  --address <host:port>    Listening address (127.0.0.1:8080 by default)
+  --stats-interval <secs>  Interval of the stats events (10 by default)
+";
+
+/// Failure classes of the CLI.
+#[derive(Error, Debug, PartialEq)]
+pub enum Failure {
+    #[error("{0}")]
+    Output(String),
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    MissingFile(String),
+    #[error("{0}")]
+    Parse(String),
+    #[error("{0}")]
+    Graph(String),
+}
+
+impl Failure {
+    /// Exit code of the failure class.
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            Failure::Output(_) => 1,
+            Failure::Usage(_) => 2,
+            Failure::MissingFile(_) => 3,
+            Failure::Parse(_) => 4,
+            Failure::Graph(_) => 5,
+        })
+    }
+}
+
+impl From<InfraError> for Failure {
+    fn from(error: InfraError) -> Self {
+        let message = format!("The graph could not be loaded: {:}", error);
+        match error {
+            InfraError::NotFileSpecified => Failure::MissingFile(message),
+            InfraError::ParseTransaction | InfraError::ParseGraph(_) | InfraError::ParseId(_) => {
+                Failure::Parse(message)
+            }
+            InfraError::Graph(_) => Failure::Graph(message),
+        }
+    }
+}
+
+impl From<ExportError> for Failure {
+    fn from(error: ExportError) -> Self {
+        match error {
+            ExportError::UnknownTransaction(_) => Failure::Usage(error.to_string()),
+            ExportError::ParseGraphml(_) => Failure::Parse(error.to_string()),
+            ExportError::Graph(_) => Failure::Graph(error.to_string()),
+        }
+    }
+}
+
+impl From<StatisticsError> for Failure {
+    fn from(error: StatisticsError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<InspectionError> for Failure {
+    fn from(error: InspectionError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<ConfirmationError> for Failure {
+    fn from(error: ConfirmationError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<MilestoneError> for Failure {
+    fn from(error: MilestoneError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<ConflictError> for Failure {
+    fn from(error: ConflictError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<SimulationError> for Failure {
+    fn from(error: SimulationError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<GeneratorError> for Failure {
+    fn from(error: GeneratorError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+impl From<TerminalError> for Failure {
+    fn from(error: TerminalError) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+/// Output formats of the `convert` and `export` commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Database,
+    Graphml,
+    Gexf,
+    Dot,
+}
+
+impl Target {
+    /// Format for the extension of `path`, database by default.
+    pub fn from_path(path: &str) -> Self {
+        match path.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("graphml") => Target::Graphml,
+            Some("gexf") => Target::Gexf,
+            Some("dot") | Some("gv") => Target::Dot,
+            _ => Target::Database,
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "database" => Ok(Target::Database),
+            "graphml" => Ok(Target::Graphml),
+            "gexf" => Ok(Target::Gexf),
+            "dot" => Ok(Target::Dot),
+            _ => Err(format!("unknown output format `{:}`", value)),
+        }
+    }
+}
+
+/// Transaction listings of the `query` command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Tips,
+    Approvers(u32),
+    PastCone(u32),
+    FutureCone(u32),
+    Filter(Expr),
+}
+
+/// Milestone selections of the `milestones` command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Milestones {
+    File(String),
+    Every(u32),
+    Filter(Expr),
+}
+
+/// Subcommands with their arguments.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Help(&'static str),
+    ListStatistics,
+    Stats {
+        path: String,
+        statistics: Vec<String>,
+        format: Format,
+        chart: bool,
+    },
+    Validate {
+        path: String,
+    },
+    Convert {
+        input: String,
+        output: String,
+        to: Target,
+    },
+    Export {
+        path: String,
+        to: Target,
+        output: String,
+        dot: DotOptions,
+    },
+    Query {
+        path: String,
+        query: Query,
+        summary: bool,
+        format: Format,
+    },
+    Inspect {
+        path: String,
+        id: u32,
+        format: Format,
+    },
+    Path {
+        path: String,
+        from: u32,
+        to: u32,
+        all: bool,
+        limit: usize,
+        format: Format,
+    },
+    Lca {
+        path: String,
+        a: u32,
+        b: u32,
+        format: Format,
+    },
+    Confirm {
+        path: String,
+        rule: Rule,
+        id: Option<u32>,
+        list: bool,
+        format: Format,
+    },
+    Milestones {
+        path: String,
+        milestones: Milestones,
+        list: bool,
+        format: Format,
+    },
+    Conflicts {
+        path: String,
+        sets: String,
+        format: Format,
+    },
+    Diff {
+        left: String,
+        right: String,
+        statistics: Vec<String>,
+        format: Format,
+    },
+    Report {
+        path: String,
+        output: String,
+    },
+    Dag {
+        path: String,
+        around: Option<(u32, u32)>,
+    },
+    Simulate {
+        path: String,
+        options: SimulationOptions,
+        format: Format,
+    },
+    Generate {
+        options: GeneratorOptions,
+        output: String,
+    },
+    Repl {
+        path: String,
+    },
+    Serve {
+        path: String,
+        address: String,
+        interval: u64,
+    },
+}
+
+/// Parsed command line: the `command` and the global options, the parent
+/// `policy`, the statistics `threads`, `ascii` terminal output and the
+/// liveness `thresholds`.
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    pub policy: ParentPolicy,
+    pub threads: usize,
+    pub ascii: bool,
+    pub thresholds: Thresholds,
+}
+
+fn help(command: &str) -> &'static str {
+    match command {
+        "stats" => STATS_USAGE,
+        "validate" => VALIDATE_USAGE,
+        "convert" => CONVERT_USAGE,
+        "export" => EXPORT_USAGE,
+        "query" => QUERY_USAGE,
+        "inspect" => INSPECT_USAGE,
+        "path" => PATH_USAGE,
+        "lca" => LCA_USAGE,
+        "confirm" => CONFIRM_USAGE,
+        "milestones" => MILESTONES_USAGE,
+        "conflicts" => CONFLICTS_USAGE,
+        "diff" => DIFF_USAGE,
+        "report" => REPORT_USAGE,
+        "dag" => DAG_USAGE,
+        "simulate" => SIMULATE_USAGE,
+        "generate" => GENERATE_USAGE,
+        "repl" => REPL_USAGE,
+        "serve" => SERVE_USAGE,
+        _ => USAGE,
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> Result<T, Failure> {
+    value
+        .parse()
+        .map_err(|_| Failure::Usage(format!("{:} does not accept `{:}`", flag, value)))
+}
+
+/// Parse the command line `args` (the program name first).
+pub fn parse(args: &[String]) -> Result<Cli, Failure> {
+    let mut iter = args.iter().skip(1).peekable();
+    let command = match iter.peek().map(|arg| arg.as_str()) {
+        None => return Err(Failure::Usage(USAGE.to_string())),
+        Some(name) if COMMANDS.contains(&name) => {
+            iter.next();
+            name
+        }
+        // the global help, not the one of the default command
+        Some("--help" | "-h") => {
+            iter.next();
+            "help"
+        }
+        _ => "stats",
+    };
+    if command == "help" {
+        let topic = iter.next().map_or("", |topic| topic.as_str());
+        return Ok(Cli {
+            command: Command::Help(help(topic)),
+            policy: ParentPolicy::default(),
+            threads: 1,
+            ascii: false,
+            thresholds: Thresholds::default(),
+        });
+    }
+
+    let mut positionals: Vec<String> = Vec::new();
+    let mut policy = ParentPolicy::default();
+    let mut threads = 1;
+    let mut ascii = false;
+    let mut thresholds = Thresholds::default();
+    let mut statistics = Vec::new();
+    let mut list_statistics = false;
+    let mut format = Format::default();
+    let mut chart = false;
+    let mut to = None;
+    let mut output = None;
+    let mut dot = DotOptions::default();
+    let mut query = None;
+    let mut summary = false;
+    let mut around = None;
+    let mut radius = 2;
+    let mut all = false;
+    let mut limit = 100;
+    let mut id = None;
+    let mut list = false;
+    let mut milestones = None;
+    let mut simulation = SimulationOptions::default();
+    let mut mcmc = false;
+    let mut alpha = 0.5;
+    let mut seed = 0;
+    let mut generator = GeneratorOptions::default();
+    let mut address = "127.0.0.1:8080".to_string();
+    let mut interval = 10;
+    while let Some(arg) = iter.next() {
+        let flag = arg.as_str();
+        let mut value = || {
+            iter.next()
+                .map(|value| value.as_str())
+                .ok_or_else(|| Failure::Usage(format!("{:} needs a value", flag)))
+        };
+        match (command, flag) {
+            (_, "--help") | (_, "-h") => {
+                return Ok(Cli {
+                    command: Command::Help(help(command)),
+                    policy,
+                    threads,
+                    ascii,
+                    thresholds,
+                })
+            }
+            (_, "--parent-refs") => policy = value()?.parse().map_err(Failure::Usage)?,
+            (_, "--threads") => {
+                threads = parse_value(flag, value()?)?;
+                if threads == 0 {
+                    return Err(Failure::Usage(
+                        "--threads needs a positive number".to_string(),
+                    ));
+                }
+            }
+            (_, "--ascii") => ascii = true,
+            (_, "--max-age") => thresholds.max_age = parse_value(flag, value()?)?,
+            (_, "--rule") => thresholds.confirmation = value()?.parse()?,
+            (_, "--confirmation-weight") => {
+                thresholds.confirmation = Rule::Weight(parse_value(flag, value()?)?)
+            }
+            ("stats", "--format")
+            | ("diff", "--format")
+            | ("query", "--format")
+            | ("inspect", "--format")
+            | ("path", "--format")
+            | ("lca", "--format")
+            | ("confirm", "--format")
+            | ("milestones", "--format")
+            | ("conflicts", "--format")
+            | ("simulate", "--format") => {
+                format = value()?.parse().map_err(Failure::Usage)?;
+            }
+            ("stats", "--stats") | ("diff", "--stats") => {
+                statistics.extend(value()?.split(',').map(|name| name.trim().to_string()));
+            }
+            ("stats", "--list-stats") => list_statistics = true,
+            ("stats", "--chart") => chart = true,
+            ("convert", "--to") | ("export", "--to") => {
+                to = Some(value()?.parse().map_err(Failure::Usage)?);
+            }
+            ("export", "--output") | ("report", "--output") | ("generate", "--output") => {
+                output = Some(value()?.to_string())
+            }
+            ("export", "--dot-tips") => dot.color_tips = true,
+            ("export", "--dot-most-referenced") => dot.color_most_referenced = true,
+            ("export", "--dot-cone") => dot.cone = Some(parse_value(flag, value()?)?),
+            ("query", "--tips") => query = Some(Query::Tips),
+            ("query", "--approvers") => {
+                query = Some(Query::Approvers(parse_value(flag, value()?)?))
+            }
+            ("query", "--past-cone") => query = Some(Query::PastCone(parse_value(flag, value()?)?)),
+            ("query", "--future-cone") => {
+                query = Some(Query::FutureCone(parse_value(flag, value()?)?))
+            }
+            ("query", "--where") => {
+                let filter = value()?
+                    .parse()
+                    .map_err(|e| Failure::Usage(format!("not correct filter: {:}", e)))?;
+                query = Some(Query::Filter(filter));
+            }
+            ("milestones", "--where") => {
+                let filter = value()?
+                    .parse()
+                    .map_err(|e| Failure::Usage(format!("not correct filter: {:}", e)))?;
+                milestones = Some(Milestones::Filter(filter));
+            }
+            ("query", "--summary") => summary = true,
+            ("serve", "--address") => address = value()?.to_string(),
+            ("serve", "--stats-interval") => {
+                interval = parse_value(flag, value()?)?;
+                if interval == 0 {
+                    return Err(Failure::Usage(
+                        "--stats-interval needs a positive number".to_string(),
+                    ));
+                }
+            }
+            ("path", "--all") => all = true,
+            ("path", "--limit") => limit = parse_value(flag, value()?)?,
+            ("confirm", "--id") => id = Some(parse_value(flag, value()?)?),
+            ("confirm", "--list") | ("milestones", "--list") => list = true,
+            ("milestones", "--file") => milestones = Some(Milestones::File(value()?.to_string())),
+            ("milestones", "--every") => {
+                milestones = Some(Milestones::Every(parse_value(flag, value()?)?))
+            }
+            ("simulate", "--strategy") | ("generate", "--strategy") => {
+                mcmc = match value()? {
+                    "uniform" => false,
+                    "mcmc" => true,
+                    strategy => {
+                        return Err(Failure::Usage(format!("unknown strategy `{:}`", strategy)))
+                    }
+                }
+            }
+            ("simulate", "--alpha") | ("generate", "--alpha") => {
+                alpha = parse_value(flag, value()?)?
+            }
+            ("simulate", "--start-depth") => {
+                simulation.start = Start::Depth(parse_value(flag, value()?)?)
+            }
+            ("simulate", "--walks") => {
+                simulation.walks = parse_value(flag, value()?)?;
+                if simulation.walks == 0 {
+                    return Err(Failure::Usage(
+                        "--walks needs a positive number".to_string(),
+                    ));
+                }
+            }
+            ("simulate", "--seed") | ("generate", "--seed") => seed = parse_value(flag, value()?)?,
+            ("generate", "--transactions") => generator.transactions = parse_value(flag, value()?)?,
+            ("generate", "--lambda") => generator.lambda = parse_value(flag, value()?)?,
+            ("generate", "--delay") => generator.delay = parse_value(flag, value()?)?,
+            ("dag", "--around") => around = Some(parse_value(flag, value()?)?),
+            ("dag", "--radius") => radius = parse_value(flag, value()?)?,
+            (_, flag) if flag.starts_with("--") => {
+                return Err(Failure::Usage(format!(
+                    "unknown option `{:}` for `{:}`, see `rust-challenge help {:}`",
+                    flag, command, command
+                )))
+            }
+            _ => positionals.push(arg.clone()),
+        }
+    }
+
+    let expected = match command {
+        "convert" | "diff" | "inspect" | "conflicts" => 2,
+        "path" | "lca" => 3,
+        "generate" => 0,
+        "stats" if list_statistics => 0,
+        _ => 1,
+    };
+    if positionals.len() != expected {
+        return Err(Failure::Usage(format!(
+            "`{:}` needs {:} argument(s), see `rust-challenge help {:}`",
+            command, expected, command
+        )));
+    }
+    if matches!(
+        command,
+        "diff"
+            | "query"
+            | "inspect"
+            | "path"
+            | "lca"
+            | "confirm"
+            | "milestones"
+            | "conflicts"
+            | "simulate"
+    ) && !matches!(format, Format::Text | Format::Json)
+    {
+        return Err(Failure::Usage(format!(
+            "`{:}` only supports the text and json formats",
+            command
+        )));
+    }
+    if chart && !statistics.is_empty() {
+        return Err(Failure::Usage(
+            "--chart draws the default statistics, it cannot be combined with --stats".to_string(),
+        ));
+    }
+    let strategy = if mcmc {
+        Strategy::Mcmc { alpha }
+    } else {
+        Strategy::Uniform
+    };
+    let mut positionals = positionals.into_iter();
+    let mut path = || positionals.next().unwrap_or_default();
+    let command = match command {
+        "stats" if list_statistics => Command::ListStatistics,
+        "stats" => Command::Stats {
+            path: path(),
+            statistics,
+            format,
+            chart,
+        },
+        "validate" => Command::Validate { path: path() },
+        "convert" => {
+            let input = path();
+            let output = path();
+            Command::Convert {
+                to: to.unwrap_or_else(|| Target::from_path(&output)),
+                input,
+                output,
+            }
+        }
+        "export" => Command::Export {
+            path: path(),
+            to: to.unwrap_or(Target::Dot),
+            output: output.unwrap_or_else(|| "-".to_string()),
+            dot,
+        },
+        "query" => Command::Query {
+            path: path(),
+            query: query.ok_or_else(|| Failure::Usage(QUERY_USAGE.to_string()))?,
+            summary,
+            format,
+        },
+        "inspect" => {
+            let db = path();
+            let id = path();
+            Command::Inspect {
+                path: db,
+                id: parse_value("inspect", &id)?,
+                format,
+            }
+        }
+        "path" => {
+            let db = path();
+            let from = path();
+            let to = path();
+            Command::Path {
+                path: db,
+                from: parse_value("path", &from)?,
+                to: parse_value("path", &to)?,
+                all,
+                limit,
+                format,
+            }
+        }
+        "lca" => {
+            let db = path();
+            let a = path();
+            let b = path();
+            Command::Lca {
+                path: db,
+                a: parse_value("lca", &a)?,
+                b: parse_value("lca", &b)?,
+                format,
+            }
+        }
+        "confirm" => Command::Confirm {
+            path: path(),
+            rule: thresholds.confirmation.clone(),
+            id,
+            list,
+            format,
+        },
+        "milestones" => Command::Milestones {
+            path: path(),
+            milestones: milestones.ok_or_else(|| Failure::Usage(MILESTONES_USAGE.to_string()))?,
+            list,
+            format,
+        },
+        "conflicts" => Command::Conflicts {
+            path: path(),
+            sets: path(),
+            format,
+        },
+        "diff" => Command::Diff {
+            left: path(),
+            right: path(),
+            statistics,
+            format,
+        },
+        "report" => Command::Report {
+            path: path(),
+            output: output.unwrap_or_else(|| "report.html".to_string()),
+        },
+        "simulate" => Command::Simulate {
+            path: path(),
+            options: SimulationOptions {
+                strategy,
+                seed,
+                ..simulation
+            },
+            format,
+        },
+        "generate" => Command::Generate {
+            options: GeneratorOptions {
+                strategy,
+                seed,
+                ..generator
+            },
+            output: output.unwrap_or_else(|| "-".to_string()),
+        },
+        "repl" => Command::Repl { path: path() },
+        "serve" => Command::Serve {
+            path: path(),
+            address,
+            interval,
+        },
+        _ => Command::Dag {
+            path: path(),
+            around: around.map(|id| (id, radius)),
+        },
+    };
+    Ok(Cli {
+        command,
+        policy,
+        threads,
+        ascii,
+        thresholds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        std::iter::once("rust-challenge")
+            .chain(line.split_whitespace())
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_subcommands() {
+        let cli = parse(&args("export db.txt --to graphml --output - --threads 2")).unwrap();
+        assert_eq!(2, cli.threads);
+        assert_eq!(
+            Command::Export {
+                path: "db.txt".to_string(),
+                to: Target::Graphml,
+                output: "-".to_string(),
+                dot: DotOptions::default(),
+            },
+            cli.command
+        );
+        let cli = parse(&args("convert ledger.graphml ledger.gexf")).unwrap();
+        assert_eq!(
+            Command::Convert {
+                input: "ledger.graphml".to_string(),
+                output: "ledger.gexf".to_string(),
+                to: Target::Gexf,
+            },
+            cli.command
+        );
+        let cli = parse(&args("inspect db.txt 3 --format json")).unwrap();
+        assert_eq!(
+            Command::Inspect {
+                path: "db.txt".to_string(),
+                id: 3,
+                format: Format::Json,
+            },
+            cli.command
+        );
+        let cli = parse(&args("path db.txt 6 1 --all --limit 2")).unwrap();
+        assert_eq!(
+            Command::Path {
+                path: "db.txt".to_string(),
+                from: 6,
+                to: 1,
+                all: true,
+                limit: 2,
+                format: Format::Text,
+            },
+            cli.command
+        );
+        let cli = parse(&args("confirm db.txt --rule weight>=3 --id 2")).unwrap();
+        assert_eq!(
+            Command::Confirm {
+                path: "db.txt".to_string(),
+                rule: Rule::Weight(3),
+                id: Some(2),
+                list: false,
+                format: Format::Text,
+            },
+            cli.command
+        );
+        assert_eq!(Rule::Weight(3), cli.thresholds.confirmation);
+        let cli = parse(&args("stats db.txt --confirmation-weight 4")).unwrap();
+        assert_eq!(Rule::Weight(4), cli.thresholds.confirmation);
+        let cli = parse(&args("milestones db.txt --every 10 --list")).unwrap();
+        assert_eq!(
+            Command::Milestones {
+                path: "db.txt".to_string(),
+                milestones: Milestones::Every(10),
+                list: true,
+                format: Format::Text,
+            },
+            cli.command
+        );
+        let cli = parse(&args(
+            "simulate db.txt --strategy mcmc --alpha 2 --walks 10",
+        ))
+        .unwrap();
+        assert_eq!(
+            Command::Simulate {
+                path: "db.txt".to_string(),
+                options: SimulationOptions {
+                    strategy: Strategy::Mcmc { alpha: 2.0 },
+                    walks: 10,
+                    ..SimulationOptions::default()
+                },
+                format: Format::Text,
+            },
+            cli.command
+        );
+        let cli = parse(&args("generate --transactions 50 --lambda 2 --seed 9")).unwrap();
+        assert_eq!(
+            Command::Generate {
+                options: GeneratorOptions {
+                    transactions: 50,
+                    lambda: 2.0,
+                    seed: 9,
+                    ..GeneratorOptions::default()
+                },
+                output: "-".to_string(),
+            },
+            cli.command
+        );
+        let cli = parse(&args("query db.txt --where tip --summary")).unwrap();
+        assert_eq!(
+            Command::Query {
+                path: "db.txt".to_string(),
+                query: Query::Filter(Expr::Flag(rust_challenge::query::Flag::Tip)),
+                summary: true,
+                format: Format::Text,
+            },
+            cli.command
+        );
+        let cli = parse(&args("query db.txt --approvers 3 --format json")).unwrap();
+        assert_eq!(
+            Command::Query {
+                path: "db.txt".to_string(),
+                query: Query::Approvers(3),
+                summary: false,
+                format: Format::Json,
+            },
+            cli.command
+        );
+    }
+
+    #[test]
+    fn should_default_to_stats() {
+        let cli = parse(&args(
+            "db.txt --parent-refs once --stats max_layer_width --max-age 7",
+        ))
+        .unwrap();
+        assert_eq!(ParentPolicy::CountOnce, cli.policy);
+        assert_eq!(7, cli.thresholds.max_age);
+        assert_eq!(
+            Command::Stats {
+                path: "db.txt".to_string(),
+                statistics: vec!["max_layer_width".to_string()],
+                format: Format::Text,
+                chart: false,
+            },
+            cli.command
+        );
+        assert_eq!(
+            Command::ListStatistics,
+            parse(&args("stats --list-stats")).unwrap().command
+        );
+        assert_eq!(
+            Command::Help(DIFF_USAGE),
+            parse(&args("diff --help")).unwrap().command
+        );
+        for flag in ["--help", "-h"] {
+            assert_eq!(
+                Command::Help(USAGE),
+                parse(&args(flag)).unwrap().command,
+                "{:}",
+                flag
+            );
+        }
+        assert_eq!(
+            Command::Help(STATS_USAGE),
+            parse(&args("db.txt --help")).unwrap().command
+        );
+    }
+
+    #[test]
+    fn should_fail_with_usage_errors() {
+        for line in [
+            "",
+            "diff db.txt",
+            "path db.txt 6",
+            "lca db.txt 5 6 --format csv",
+            "confirm db.txt --rule weight",
+            "milestones db.txt --list",
+            "conflicts db.txt",
+            "simulate db.txt --strategy greedy",
+            "simulate db.txt --walks 0",
+            "validate db.txt --chart",
+            "db.txt --stats avg_depth --chart",
+            "stats db.txt --threads 0",
+            "query db.txt",
+            "query db.txt --where depth>",
+            "diff a.txt b.txt --format csv",
+        ] {
+            let failure = parse(&args(line)).unwrap_err();
+            assert!(matches!(failure, Failure::Usage(_)), "{:}", line);
+            assert_eq!(ExitCode::from(2), failure.exit_code());
+        }
+        assert_eq!(
+            Failure::MissingFile(
+                "The graph could not be loaded: not correct path file".to_string()
+            ),
+            Failure::from(InfraError::NotFileSpecified)
+        );
+    }
+}
@@ -3,6 +3,7 @@
 // it need  more responsabilities and for this reason,
 // it was moved in another module `graph.rs`
 use std::fmt;
+use std::str::FromStr;
 
 /// Structure for the transaction - node representation
 /// it includes `id` `timestamp` both `parents` and its metrics
@@ -15,14 +16,30 @@ pub struct Transaction {
     pub metrics: TransactionMetrics,
 }
 
+/// Policy for counting in references when a transaction uses the same
+/// node as left and right parent.
+///
+/// - `CountTwice` each side is an in reference (default behaviour).
+/// - `CountOnce` the shared parent receives a single in reference.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParentPolicy {
+    #[default]
+    CountTwice,
+    CountOnce,
+}
+
 /// Transaction metrics
 ///
 /// - `depth` for a transaction  from the root node
 /// - `in_reference` current in references
+/// - `generation` length of the longest path from the root node
+/// - `first_approval` earliest timestamp of the transactions referencing it
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct TransactionMetrics {
     pub depth: u32,
     pub in_reference: u32,
+    pub generation: u32,
+    pub first_approval: Option<u32>,
 }
 /// Structure for saving graph metrics.
 ///
@@ -54,6 +71,36 @@ impl fmt::Display for GeneralMetrics {
     }
 }
 
+impl TransactionMetrics {
+    /// It registers an approval issued at `timestamp`.
+    pub fn approve(&mut self, timestamp: u32) {
+        self.first_approval = Some(
+            self.first_approval
+                .map_or(timestamp, |first| first.min(timestamp)),
+        );
+    }
+}
+
+impl FromStr for ParentPolicy {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "twice" => Ok(ParentPolicy::CountTwice),
+            "once" => Ok(ParentPolicy::CountOnce),
+            _ => Err(format!("unknown parent policy `{:}`", value)),
+        }
+    }
+}
+
+impl fmt::Display for ParentPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParentPolicy::CountTwice => write!(f, "twice"),
+            ParentPolicy::CountOnce => write!(f, "once"),
+        }
+    }
+}
+
 impl Transaction {
     /// Constructor for a transaction. It has an unique id `id` with its parents `left_parent` and
     /// `right_parent` and its timestamp `timestamp`
@@ -65,6 +112,12 @@ impl Transaction {
             metrics: Default::default(),
         }
     }
+
+    /// It checks if the transaction references the same node as left
+    /// and right parent.
+    pub fn has_same_parents(&self) -> bool {
+        matches!(self.parents, Some((left, right)) if left == right)
+    }
 }
 
 impl fmt::Display for Transaction {
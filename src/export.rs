@@ -0,0 +1,436 @@
+// Export module. It renders the graph in formats of external graph tools
+// for visualising and analysing the ledger.
+use thiserror::Error;
+
+use crate::domain::Transaction;
+use crate::graph::{Graph, GraphError};
+
+/// Set of possible export (and import) errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum ExportError {
+    #[error("unknown transaction id=`{0}`")]
+    UnknownTransaction(u32),
+    #[error("not correct graphml :`{0}`")]
+    ParseGraphml(String),
+    #[error("not correct graph :`{0}`")]
+    Graph(#[from] GraphError),
+}
+
+// Nodes of graph `graph` sorted by id, for a stable output.
+fn sorted_nodes(graph: &Graph) -> Vec<&Transaction> {
+    let mut nodes = graph.nodes.values().collect::<Vec<&Transaction>>();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+// Node attributes exported to the XML formats: name and value.
+fn node_attributes(node: &Transaction) -> [(&'static str, Option<u32>); 5] {
+    [
+        ("timestamp", Some(node.timestamp)),
+        ("depth", Some(node.metrics.depth)),
+        ("in_reference", Some(node.metrics.in_reference)),
+        ("generation", Some(node.metrics.generation)),
+        ("first_approval", node.metrics.first_approval),
+    ]
+}
+
+// Edges of graph `graph` from every transaction to its parents with their
+// kind (`left` or `right`).
+fn edges(graph: &Graph) -> Vec<(u32, u32, &'static str)> {
+    sorted_nodes(graph)
+        .iter()
+        .filter_map(|node| node.parents.map(|parents| (node.id, parents)))
+        .flat_map(|(id, (left, right))| [(id, left, "left"), (id, right, "right")])
+        .collect()
+}
+
+/// Graphviz DOT rendering.
+pub mod dot {
+    use std::collections::BTreeMap;
+
+    use super::{sorted_nodes, ExportError};
+    use crate::domain::Transaction;
+    use crate::graph::Graph;
+
+    const TIP_COLOR: &str = "lightblue";
+    const MOST_REFERENCED_COLOR: &str = "orange";
+    const CONE_COLOR: &str = "red";
+    const PAST_CONE_COLOR: &str = "khaki";
+    const FUTURE_CONE_COLOR: &str = "palegreen";
+
+    /// Options for highlighting nodes:
+    ///
+    /// - `color_tips` transactions without approvers.
+    /// - `color_most_referenced` transaction with most in references.
+    /// - `cone` transaction whose past and future cones are colored.
+    ///
+    /// When a node matches several options, the most referenced color wins
+    /// over the cone colors and these over the tip color.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct DotOptions {
+        pub color_tips: bool,
+        pub color_most_referenced: bool,
+        pub cone: Option<u32>,
+    }
+
+    fn label(node: &Transaction) -> String {
+        format!(
+            "{:}\\nt={:}\\ndepth={:} ref={:} gen={:}",
+            node.id,
+            node.timestamp,
+            node.metrics.depth,
+            node.metrics.in_reference,
+            node.metrics.generation
+        )
+    }
+
+    /// DOT document of graph `graph`. Nodes are ranked by depth and edges
+    /// point from a transaction to its parents, solid for the left parent
+    /// and dashed for the right one.
+    pub fn export(graph: &Graph, options: &DotOptions) -> Result<String, ExportError> {
+        let cones = match options.cone {
+            Some(id) if !graph.nodes.contains_key(&id) => {
+                return Err(ExportError::UnknownTransaction(id))
+            }
+            Some(id) => Some((id, graph.past_cone(id), graph.future_cone(id))),
+            None => None,
+        };
+        let color = |node: &Transaction| -> Option<&str> {
+            if options.color_most_referenced
+                && node.id == graph.metrics.most_in_reference_transaction
+            {
+                return Some(MOST_REFERENCED_COLOR);
+            }
+            if let Some((id, past, future)) = &cones {
+                if node.id == *id {
+                    return Some(CONE_COLOR);
+                }
+                if past.contains(&node.id) {
+                    return Some(PAST_CONE_COLOR);
+                }
+                if future.contains(&node.id) {
+                    return Some(FUTURE_CONE_COLOR);
+                }
+            }
+            if options.color_tips && graph.is_tip(node.id) {
+                return Some(TIP_COLOR);
+            }
+            None
+        };
+
+        let sorted_nodes = sorted_nodes(graph);
+        let mut by_depth: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for node in sorted_nodes.iter() {
+            by_depth
+                .entry(node.metrics.depth)
+                .or_default()
+                .push(node.id);
+        }
+
+        let mut output = String::from("digraph ledger {\n    rankdir=RL;\n    node [shape=box];\n");
+        for (depth, ids) in by_depth.iter() {
+            let ids = ids
+                .iter()
+                .map(|id| format!("\"{:}\";", id))
+                .collect::<Vec<String>>()
+                .join(" ");
+            output += format!("    {{ rank=same; /* depth {:} */ {:} }}\n", depth, ids).as_str();
+        }
+        for node in sorted_nodes.iter() {
+            let style = color(node)
+                .map(|color| format!(", style=filled, fillcolor={:}", color))
+                .unwrap_or_default();
+            output += format!(
+                "    \"{:}\" [label=\"{:}\"{:}];\n",
+                node.id,
+                label(node),
+                style
+            )
+            .as_str();
+        }
+        for node in sorted_nodes.iter() {
+            if let Some((left, right)) = node.parents {
+                output +=
+                    format!("    \"{:}\" -> \"{:}\" [label=\"L\"];\n", node.id, left).as_str();
+                output += format!(
+                    "    \"{:}\" -> \"{:}\" [label=\"R\", style=dashed];\n",
+                    node.id, right
+                )
+                .as_str();
+            }
+        }
+        output += "}\n";
+        Ok(output)
+    }
+}
+
+/// GraphML rendering and import. The metrics and the timestamp are node
+/// attributes and the parent kind (`left` or `right`) is an edge attribute.
+pub mod graphml {
+    use std::collections::BTreeMap;
+
+    use super::{edges, node_attributes, sorted_nodes, ExportError};
+    use crate::domain::ParentPolicy;
+    use crate::graph::Graph;
+
+    /// GraphML document of graph `graph`.
+    pub fn export(graph: &Graph) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output += "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n";
+        output += "  <key id=\"parent_policy\" for=\"graph\" attr.name=\"parent_policy\" attr.type=\"string\"/>\n";
+        for (name, _) in node_attributes(&graph.nodes[&1]) {
+            output += format!(
+                "  <key id=\"{:}\" for=\"node\" attr.name=\"{:}\" attr.type=\"long\"/>\n",
+                name, name
+            )
+            .as_str();
+        }
+        output += "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n";
+        output += "  <graph id=\"ledger\" edgedefault=\"directed\">\n";
+        output += format!("    <data key=\"parent_policy\">{}</data>\n", graph.policy).as_str();
+        for node in sorted_nodes(graph) {
+            output += format!("    <node id=\"{:}\">", node.id).as_str();
+            for (name, value) in node_attributes(node) {
+                if let Some(value) = value {
+                    output += format!("<data key=\"{:}\">{}</data>", name, value).as_str();
+                }
+            }
+            output += "</node>\n";
+        }
+        for (source, target, kind) in edges(graph) {
+            output += format!(
+                "    <edge source=\"{:}\" target=\"{:}\"><data key=\"kind\">{}</data></edge>\n",
+                source, target, kind
+            )
+            .as_str();
+        }
+        output += "  </graph>\n</graphml>\n";
+        output
+    }
+
+    fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+        let start = tag.find(format!(" {:}=\"", name).as_str())? + name.len() + 3;
+        let end = tag[start..].find('"')? + start;
+        Some(&tag[start..end])
+    }
+
+    fn number(value: &str) -> Result<u32, ExportError> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| ExportError::ParseGraphml(format!("`{:}` is not a number", value)))
+    }
+
+    /// Graph from a GraphML document `input` written by `export`. Metrics
+    /// are recalculated, transaction ids must be consecutive from 1.
+    pub fn import(input: &str) -> Result<Graph, ExportError> {
+        let mut policy = ParentPolicy::default();
+        let mut timestamps: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut parents: BTreeMap<u32, (Option<u32>, Option<u32>)> = BTreeMap::new();
+        let mut current_node = None;
+        let mut current_edge = None;
+        for piece in input.split('<').skip(1) {
+            let (tag, text) = piece
+                .split_once('>')
+                .ok_or_else(|| ExportError::ParseGraphml("not closed tag".to_string()))?;
+            if tag.starts_with("node ") {
+                let id = number(attribute(tag, "id").unwrap_or_default())?;
+                timestamps.insert(id, 0);
+                current_node = Some(id);
+            } else if tag.starts_with("edge ") {
+                let source = number(attribute(tag, "source").unwrap_or_default())?;
+                let target = number(attribute(tag, "target").unwrap_or_default())?;
+                current_edge = Some((source, target));
+            } else if tag == "/node" {
+                current_node = None;
+            } else if tag == "/edge" {
+                current_edge = None;
+            } else if tag.starts_with("data ") {
+                match (attribute(tag, "key"), current_node, current_edge) {
+                    (Some("parent_policy"), None, None) => {
+                        policy = text.trim().parse().map_err(ExportError::ParseGraphml)?
+                    }
+                    (Some("timestamp"), Some(id), None) => {
+                        timestamps.insert(id, number(text)?);
+                    }
+                    (Some("kind"), None, Some((source, target))) => {
+                        let entry = parents.entry(source).or_default();
+                        match text.trim() {
+                            "left" => entry.0 = Some(target),
+                            "right" => entry.1 = Some(target),
+                            kind => {
+                                return Err(ExportError::ParseGraphml(format!(
+                                    "unknown edge kind `{:}`",
+                                    kind
+                                )))
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if timestamps.keys().copied().ne(1..=timestamps.len() as u32) {
+            return Err(ExportError::ParseGraphml(
+                "node ids are not consecutive from 1".to_string(),
+            ));
+        }
+        let nodes = timestamps
+            .iter()
+            .skip(1)
+            .map(|(id, &timestamp)| match parents.get(id) {
+                Some(&(Some(left), Some(right))) => Ok((left, right, timestamp)),
+                _ => Err(ExportError::ParseGraphml(format!(
+                    "node id=`{:}` without left and right parents",
+                    id
+                ))),
+            })
+            .collect::<Result<Vec<(u32, u32, u32)>, ExportError>>()?;
+        Ok(Graph::from_nodes(nodes, policy)?)
+    }
+}
+
+/// GEXF 1.3 rendering (Gephi). The metrics and the timestamp are node
+/// attributes and the parent kind (`left` or `right`) is an edge attribute.
+pub mod gexf {
+    use super::{edges, node_attributes, sorted_nodes};
+    use crate::graph::Graph;
+
+    /// GEXF document of graph `graph`.
+    pub fn export(graph: &Graph) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output += "<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n";
+        output += "  <graph defaultedgetype=\"directed\" mode=\"static\">\n";
+        output += "    <attributes class=\"node\">\n";
+        for (index, (name, _)) in node_attributes(&graph.nodes[&1]).iter().enumerate() {
+            output += format!(
+                "      <attribute id=\"{:}\" title=\"{:}\" type=\"long\"/>\n",
+                index, name
+            )
+            .as_str();
+        }
+        output += "    </attributes>\n";
+        output += "    <attributes class=\"edge\">\n";
+        output += "      <attribute id=\"0\" title=\"kind\" type=\"string\"/>\n";
+        output += "    </attributes>\n";
+        output += "    <nodes>\n";
+        for node in sorted_nodes(graph) {
+            output += format!(
+                "      <node id=\"{:}\" label=\"{:}\"><attvalues>",
+                node.id, node.id
+            )
+            .as_str();
+            for (index, (_, value)) in node_attributes(node).iter().enumerate() {
+                if let Some(value) = value {
+                    output +=
+                        format!("<attvalue for=\"{:}\" value=\"{:}\"/>", index, value).as_str();
+                }
+            }
+            output += "</attvalues></node>\n";
+        }
+        output += "    </nodes>\n";
+        output += "    <edges>\n";
+        for (index, (source, target, kind)) in edges(graph).iter().enumerate() {
+            output += format!(
+                "      <edge id=\"{:}\" source=\"{:}\" target=\"{:}\"><attvalues><attvalue for=\"0\" value=\"{:}\"/></attvalues></edge>\n",
+                index, source, target, kind
+            )
+            .as_str();
+        }
+        output += "    </edges>\n  </graph>\n</gexf>\n";
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dot::*;
+    use super::{gexf, graphml, ExportError};
+    use crate::domain::ParentPolicy;
+    use crate::graph::Graph;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    #[test]
+    fn should_export_graph_as_dot() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = export(&graph, &Default::default()).unwrap();
+        assert!(output.starts_with("digraph ledger {\n"));
+        assert!(output.contains("{ rank=same; /* depth 1 */ \"2\"; \"3\"; }"));
+        assert!(output.contains("\"6\" [label=\"6\\nt=3\\ndepth=2 ref=0 gen=3\"];"));
+        assert!(output.contains("\"6\" -> \"3\" [label=\"L\"];"));
+        assert!(output.contains("\"6\" -> \"4\" [label=\"R\", style=dashed];"));
+        assert!(!output.contains("fillcolor"));
+    }
+
+    #[test]
+    fn should_color_tips_most_referenced_and_cone() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let options = DotOptions {
+            color_tips: true,
+            color_most_referenced: true,
+            cone: Some(4),
+        };
+        let output = export(&graph, &options).unwrap();
+        assert!(output.contains(
+            "\"1\" [label=\"1\\nt=0\\ndepth=0 ref=3 gen=0\", style=filled, fillcolor=orange];"
+        ));
+        assert!(output.contains("fillcolor=red];"));
+        assert!(output.contains(
+            "\"2\" [label=\"2\\nt=0\\ndepth=1 ref=3 gen=1\", style=filled, fillcolor=khaki];"
+        ));
+        assert!(output.contains(
+            "\"6\" [label=\"6\\nt=3\\ndepth=2 ref=0 gen=3\", style=filled, fillcolor=palegreen];"
+        ));
+        assert!(output.contains(
+            "\"5\" [label=\"5\\nt=2\\ndepth=2 ref=0 gen=3\", style=filled, fillcolor=lightblue];"
+        ));
+    }
+
+    #[test]
+    fn should_fail_exporting_unknown_cone() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let options = DotOptions {
+            cone: Some(42),
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(ExportError::UnknownTransaction(42)),
+            export(&graph, &options)
+        );
+    }
+
+    #[test]
+    fn should_round_trip_graph_through_graphml() {
+        let graph = Graph::from_nodes(TEST.to_vec(), ParentPolicy::CountOnce).unwrap();
+        let output = graphml::export(&graph);
+        assert!(output.contains(
+            "<node id=\"4\"><data key=\"timestamp\">1</data><data key=\"depth\">2</data>"
+        ));
+        assert!(output
+            .contains("<edge source=\"6\" target=\"4\"><data key=\"kind\">right</data></edge>"));
+        assert_eq!(graph, graphml::import(&output).unwrap());
+    }
+
+    #[test]
+    fn should_fail_importing_graphml_without_parents() {
+        let input =
+            "<graphml><graph><node id=\"1\"></node><node id=\"2\"></node></graph></graphml>";
+        assert_eq!(
+            Err(ExportError::ParseGraphml(
+                "node id=`2` without left and right parents".to_string()
+            )),
+            graphml::import(input)
+        );
+    }
+
+    #[test]
+    fn should_export_graph_as_gexf() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = gexf::export(&graph);
+        assert!(output.contains("<attribute id=\"2\" title=\"in_reference\" type=\"long\"/>"));
+        assert!(output
+            .contains("<node id=\"6\" label=\"6\"><attvalues><attvalue for=\"0\" value=\"3\"/>"));
+        assert!(output.contains("<edge id=\"9\" source=\"6\" target=\"4\"><attvalues><attvalue for=\"0\" value=\"right\"/>"));
+    }
+}
@@ -0,0 +1,268 @@
+// Generator module. It builds synthetic ledgers following the usual tangle
+// model: transactions arrive as a Poisson process of rate `lambda`, each one
+// sees the ledger as it was `delay` time units before (the network delay)
+// and selects its two parents among the tips it sees, uniformly or with
+// MCMC random walks.
+use std::collections::{HashMap, VecDeque};
+
+use thiserror::Error;
+
+use crate::graph::{Graph, GraphError};
+use crate::simulation::{self, Rng, Strategy};
+
+/// Window of the MCMC walks, in transactions behind the latest visible
+/// one. The walks start at the oldest ancestor of the latest visible
+/// transaction, following left parents, inside the window.
+pub const WALK_WINDOW: u32 = 100;
+
+/// Set of possible generator errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum GeneratorError {
+    #[error("lambda must be a finite positive number, found `{0}`")]
+    InvalidRate(f64),
+    #[error("delay must be a finite non negative number, found `{0}`")]
+    InvalidDelay(f64),
+    #[error("alpha must be a finite non negative number, found `{0}`")]
+    InvalidAlpha(f64),
+    #[error(transparent)]
+    Graph(#[from] GraphError),
+}
+
+/// Ledger model: the number of `transactions` after the root, their
+/// arrival rate `lambda` per time unit, the network `delay` in time units,
+/// the tip selection `strategy` and the `seed` of the generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorOptions {
+    pub transactions: u32,
+    pub lambda: f64,
+    pub delay: f64,
+    pub strategy: Strategy,
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            transactions: 1000,
+            lambda: 4.0,
+            delay: 1.0,
+            strategy: Strategy::Uniform,
+            seed: 0,
+        }
+    }
+}
+
+// Ledger as seen by the new transactions: the first `visible` ids (ids
+// follow the arrival order), their tips and, for MCMC, the cumulative
+// weights of the transactions the walks can reach.
+struct View {
+    visible: u32,
+    parents: Vec<(u32, u32)>,
+    approvers: Vec<Vec<u32>>,
+    tips: Vec<u32>,
+    tip_positions: HashMap<u32, usize>,
+    weights: Vec<u64>,
+    // last revealed transaction whose past cone included each transaction
+    visits: Vec<u32>,
+}
+
+impl View {
+    fn new(transactions: u32, mcmc: bool) -> Self {
+        let size = transactions as usize + 2;
+        View {
+            visible: 1,
+            parents: vec![(0, 0); size],
+            approvers: vec![Vec::new(); size],
+            tips: vec![1],
+            tip_positions: HashMap::from([(1, 0)]),
+            weights: if mcmc { vec![1; size] } else { Vec::new() },
+            visits: if mcmc { vec![0; size] } else { Vec::new() },
+        }
+    }
+
+    fn remove_tip(&mut self, id: u32) {
+        if let Some(position) = self.tip_positions.remove(&id) {
+            self.tips.swap_remove(position);
+            if let Some(moved) = self.tips.get(position) {
+                self.tip_positions.insert(*moved, position);
+            }
+        }
+    }
+
+    // Oldest transaction the walks can visit, it never decreases.
+    fn window_start(&self) -> u32 {
+        self.visible.saturating_sub(WALK_WINDOW).max(1)
+    }
+
+    fn walk_start(&self) -> u32 {
+        let window_start = self.window_start();
+        let mut id = self.visible;
+        while id > 1 && self.parents[id as usize].0 >= window_start {
+            id = self.parents[id as usize].0;
+        }
+        id
+    }
+
+    // Make the next transaction visible to the new arrivals.
+    fn reveal(&mut self, mcmc: bool) {
+        self.visible += 1;
+        let id = self.visible;
+        let (left, right) = self.parents[id as usize];
+        self.remove_tip(left);
+        self.remove_tip(right);
+        self.tip_positions.insert(id, self.tips.len());
+        self.tips.push(id);
+        if mcmc {
+            // the walks never leave the window, older weights are unused
+            let start = self.window_start();
+            let mut pending = vec![left, right];
+            while let Some(ancestor) = pending.pop() {
+                if ancestor < start || self.visits[ancestor as usize] == id {
+                    continue;
+                }
+                self.visits[ancestor as usize] = id;
+                self.weights[ancestor as usize] += 1;
+                if ancestor > 1 {
+                    let (left, right) = self.parents[ancestor as usize];
+                    pending.extend([left, right]);
+                }
+            }
+        }
+    }
+
+    fn select(&self, strategy: Strategy, rng: &mut Rng) -> u32 {
+        if strategy == Strategy::Uniform {
+            return self.tips[rng.below(self.tips.len() as u64) as usize];
+        }
+        let weight = |id: u32| self.weights[id as usize];
+        let mut id = self.walk_start();
+        loop {
+            let approvers = &self.approvers[id as usize];
+            // approvers are sorted by id, the visible ones come first
+            let visible = approvers.partition_point(|approver| *approver <= self.visible);
+            if visible == 0 {
+                return id;
+            }
+            id = simulation::step(&approvers[..visible], weight(id), weight, strategy, rng);
+        }
+    }
+}
+
+/// Synthetic ledger following the model `options`.
+pub fn generate(options: &GeneratorOptions) -> Result<Graph, GeneratorError> {
+    if !options.lambda.is_finite() || options.lambda <= 0.0 {
+        return Err(GeneratorError::InvalidRate(options.lambda));
+    }
+    if !options.delay.is_finite() || options.delay < 0.0 {
+        return Err(GeneratorError::InvalidDelay(options.delay));
+    }
+    if let Strategy::Mcmc { alpha } = options.strategy {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(GeneratorError::InvalidAlpha(alpha));
+        }
+    }
+    let mcmc = options.strategy != Strategy::Uniform;
+    let mut rng = Rng::new(options.seed);
+    let mut view = View::new(options.transactions, mcmc);
+    // arrival times of the transactions not visible yet
+    let mut hidden: VecDeque<f64> = VecDeque::new();
+    let mut values = Vec::with_capacity(options.transactions as usize);
+    let mut time = 0.0;
+    for index in 0..options.transactions {
+        time += -(1.0 - rng.next_f64()).ln() / options.lambda;
+        while hidden
+            .front()
+            .is_some_and(|arrival| arrival + options.delay <= time)
+        {
+            hidden.pop_front();
+            view.reveal(mcmc);
+        }
+        let id = index + 2;
+        let left = view.select(options.strategy, &mut rng);
+        let right = view.select(options.strategy, &mut rng);
+        view.parents[id as usize] = (left, right);
+        view.approvers[left as usize].push(id);
+        if right != left {
+            view.approvers[right as usize].push(id);
+        }
+        hidden.push_back(time);
+        values.push((left, right, time as u32));
+    }
+    Ok(Graph::try_from(values)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_generate_reproducible_ledgers() {
+        let options = GeneratorOptions {
+            transactions: 300,
+            seed: 11,
+            ..GeneratorOptions::default()
+        };
+        let graph = generate(&options).unwrap();
+        assert_eq!(301, graph.nodes.len());
+        assert_eq!(graph, generate(&options).unwrap());
+        assert_ne!(
+            graph,
+            generate(&GeneratorOptions {
+                seed: 12,
+                ..options.clone()
+            })
+            .unwrap()
+        );
+        // about lambda transactions per time unit
+        let last = graph
+            .nodes
+            .values()
+            .map(|node| node.timestamp)
+            .max()
+            .unwrap();
+        assert!((60..=90).contains(&last), "last timestamp {:}", last);
+    }
+
+    #[test]
+    fn should_only_reference_visible_transactions() {
+        let options = GeneratorOptions {
+            transactions: 200,
+            lambda: 2.0,
+            delay: 3.0,
+            strategy: Strategy::Mcmc { alpha: 0.5 },
+            seed: 5,
+        };
+        let graph = generate(&options).unwrap();
+        for node in graph.nodes.values() {
+            if let Some((left, right)) = node.parents {
+                for parent in [left, right] {
+                    let parent_timestamp = graph.nodes[&parent].timestamp as f64;
+                    assert!(
+                        parent == 1
+                            || parent_timestamp + options.delay <= node.timestamp as f64 + 1.0
+                    );
+                }
+            }
+        }
+        assert!(graph.tips().len() > 1);
+    }
+
+    #[test]
+    fn should_fail_with_invalid_model() {
+        let invalid = |options| generate(&options).unwrap_err();
+        assert_eq!(
+            GeneratorError::InvalidRate(0.0),
+            invalid(GeneratorOptions {
+                lambda: 0.0,
+                ..GeneratorOptions::default()
+            })
+        );
+        assert_eq!(
+            GeneratorError::InvalidDelay(-1.0),
+            invalid(GeneratorOptions {
+                delay: -1.0,
+                ..GeneratorOptions::default()
+            })
+        );
+    }
+}
@@ -9,7 +9,8 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 
-use crate::graph::Graph;
+use crate::domain::ParentPolicy;
+use crate::graph::{Graph, GraphError};
 
 use thiserror::Error;
 
@@ -22,6 +23,10 @@ pub enum InfraError {
     ParseGraph(String),
     #[error("not correct path file")]
     NotFileSpecified,
+    #[error("not correct graph: `{0}`")]
+    Graph(#[from] GraphError),
+    #[error("not correct transaction id `{0}`")]
+    ParseId(String),
 }
 
 fn parse_node(line: String) -> Result<(u32, u32, u32), InfraError> {
@@ -62,6 +67,11 @@ impl DBRepository {
     /// Graph load function. It throws different errors if something works
     /// wrong (File is removed or modified).
     pub fn load(&self) -> Result<Graph, InfraError> {
+        self.load_with_policy(ParentPolicy::default())
+    }
+
+    /// Graph load function, duplicated parents are counted following `policy`.
+    pub fn load_with_policy(&self, policy: ParentPolicy) -> Result<Graph, InfraError> {
         let file = File::open(self.path_buf.clone()).map_err(|_| InfraError::NotFileSpecified)?;
         let reader = BufReader::new(file);
 
@@ -88,13 +98,49 @@ impl DBRepository {
                 "First line was not correct".to_string(),
             ));
         }
-        let graph = Graph::try_from(nodes)
-            .map_err(|_| InfraError::ParseGraph("impossible add node in the graph".to_string()))?;
+        let graph = Graph::from_nodes(nodes, policy)?;
 
         Ok(graph)
     }
 }
 
+/// Groups of transaction ids of the file at `path_str`, one group per line
+/// with the ids separated by spaces or commas. Empty lines and lines
+/// starting with `#` are skipped.
+pub fn load_groups(path_str: &str) -> Result<Vec<Vec<u32>>, InfraError> {
+    let file = File::open(path_str).map_err(|_| InfraError::NotFileSpecified)?;
+    let mut groups = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|_| InfraError::NotFileSpecified)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let group = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map_err(|_| InfraError::ParseId(id.to_string())))
+            .collect::<Result<Vec<u32>, InfraError>>()?;
+        groups.push(group);
+    }
+    Ok(groups)
+}
+
+/// Database text of graph `graph`: the number of transactions and one line
+/// `left right timestamp` per transaction after the root, in id order.
+pub fn to_database(graph: &Graph) -> String {
+    let mut ids = graph.nodes.keys().copied().collect::<Vec<u32>>();
+    ids.sort();
+    let mut output = format!("{:}\n", ids.len().saturating_sub(1));
+    for id in ids {
+        let node = &graph.nodes[&id];
+        if let Some((left, right)) = node.parents {
+            output += format!("{:} {:} {:}\n", left, right, node.timestamp).as_str();
+        }
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,6 +172,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::explicit_auto_deref)]
     fn should_load_a_node_from_database_file() {
         let input_content: &str = "1\n1 1 0";
         let dir = tempdir().unwrap();
@@ -170,6 +217,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_load_groups_of_ids() {
+        let dir = tempdir().unwrap();
+        let file_path = create_temp_file("# milestones\n3, 6\n\n9 12 15\n", &dir);
+        assert_eq!(
+            Ok(vec![vec![3, 6], vec![9, 12, 15]]),
+            load_groups(file_path.to_str().unwrap())
+        );
+        let file_path = create_temp_file("3 x\n", &dir);
+        assert_eq!(
+            Err(InfraError::ParseId("x".to_string())),
+            load_groups(file_path.to_str().unwrap())
+        );
+    }
+
     #[test]
     fn should_fail_parse_nodes() {
         let input_content: &str = "1\n1 x";
@@ -179,6 +241,28 @@ mod tests {
         assert_eq!(Err(InfraError::ParseTransaction), repo.load());
     }
 
+    #[test]
+    fn should_fail_with_graph_error() {
+        let input_content: &str = "1\n3 1 0";
+        let dir = tempdir().unwrap();
+        let file_path = create_temp_file(input_content, &dir);
+        let repo = DBRepository::new(file_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            Err(InfraError::Graph(GraphError::ParentNotFound)),
+            repo.load()
+        );
+    }
+
+    #[test]
+    fn should_write_loaded_database() {
+        let input_content: &str = "5\n1 1 0\n1 2 0\n2 2 1\n3 3 2\n3 4 3\n";
+        let dir = tempdir().unwrap();
+        let file_path = create_temp_file(input_content, &dir);
+        let repo = DBRepository::new(file_path.to_str().unwrap()).unwrap();
+        let graph = repo.load().unwrap();
+        assert_eq!(input_content, to_database(&graph));
+    }
+
     #[test]
     fn should_fail_open_file() {
         let repo = DBRepository::new("notexist");
@@ -0,0 +1,15 @@
+// Library crate of the ledger statistics. It exposes the domain, graph,
+// infrastructure and services modules to the CLI (`main.rs`) and to the
+// benchmarks.
+pub mod domain;
+pub mod export;
+pub mod generator;
+pub mod graph;
+pub mod infra;
+pub mod output;
+pub mod query;
+pub mod report;
+pub mod server;
+pub mod services;
+pub mod simulation;
+pub mod terminal;
@@ -1,13 +1,32 @@
 /// Entrypoint module, it includes the CLI and its UI for display results
-mod domain;
-mod graph;
-mod infra;
-mod services;
-
+use std::collections::HashSet;
 use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_challenge::export::{dot, gexf, graphml};
+use rust_challenge::generator;
+use rust_challenge::graph::Graph;
+use rust_challenge::infra::{self, DBRepository};
+use rust_challenge::output::json::{self, Json};
+use rust_challenge::output::{csv, prometheus, Format};
+use rust_challenge::query;
+use rust_challenge::report;
+use rust_challenge::server::{self, Server};
+use rust_challenge::services::*;
+use rust_challenge::simulation::{self, Simulation};
+use rust_challenge::terminal::{self, TerminalOptions};
+
+mod cli;
+mod repl;
 
-use infra::DBRepository;
-use services::*;
+use cli::{Cli, Command, Failure, Milestones, Query, Target};
+
+/// Transactions listed in the text output of a statistic.
+const MAX_LISTED: usize = 20;
 
 fn display(stats: &dto::Statistics) {
     let mut output = String::new();
@@ -20,45 +39,709 @@ fn display(stats: &dto::Statistics) {
         stats.most_referenced_transaction
     )
     .as_str();
-    output += format_timestamps(&stats.range_timestamps).as_str();
+    output += format!(
+        "> TRANS SAME PARENTS: {:}\n",
+        stats.same_parent_transactions
+    )
+    .as_str();
+    output += format!(
+        "> TRANS DISTINCT PARENTS: {:}\n",
+        stats.distinct_parent_transactions
+    )
+    .as_str();
+    output += format!("> MAX LAYER WIDTH: {:}\n", stats.max_layer_width).as_str();
+    output += format!("> MAX WIDTH: {:}\n", stats.max_width).as_str();
+    output += format_depths(&stats.transactions_by_depth).as_str();
+    output += format_ranges(
+        "TIMESTAMPS --> NUM TRANS",
+        " trans",
+        &stats.range_timestamps,
+    )
+    .as_str();
+    output += format_ranges(
+        "WIDTH OVER TIME --> OPEN TIPS",
+        " tips",
+        &stats.width_over_time,
+    )
+    .as_str();
     print!("{:}", output);
 }
-fn format_timestamps(timestamps: &Vec<(u32, u64)>) -> String {
+fn display_entries(entries: &[dto::Entry]) {
     let mut output = String::new();
-    output += "> TIMESTAMPS --> NUM TRANS \n";
-    for (range, count) in timestamps.iter() {
+    for entry in entries.iter() {
+        output += match &entry.value {
+            dto::Value::Float(value) => format!("> {:}: {:.2}\n", entry.label, value),
+            dto::Value::Count(value) => format!("> {:}: {:}\n", entry.label, value),
+            dto::Value::Transaction(id) => format!("> {:}: {:}\n", entry.label, id),
+            dto::Value::ByDepth(widths) => {
+                let mut lines = format!("> {:} \n", entry.label);
+                for (depth, count) in widths.iter() {
+                    lines += format!("- {:} --> {:}\n", depth, count).as_str();
+                }
+                lines
+            }
+            dto::Value::ByTimeRange(ranges) => format_ranges(&entry.label, "", ranges),
+            dto::Value::Transactions(ids) => format_transactions(&entry.label, ids),
+        }
+        .as_str();
+    }
+    print!("{:}", output);
+}
+
+// Number of transactions `ids` and the first `MAX_LISTED` of them.
+fn format_transactions(label: &str, ids: &[u32]) -> String {
+    let mut output = format!("> {:}: {:} trans\n", label, ids.len());
+    if !ids.is_empty() {
+        let listed = &ids[..ids.len().min(MAX_LISTED)];
+        let more = if ids.len() > MAX_LISTED { ", ..." } else { "" };
+        output += format!("- {:}{:}\n", join(listed), more).as_str();
+    }
+    output
+}
+fn format_depths(widths: &[(u32, u64)]) -> String {
+    let mut output = String::new();
+    output += "> TXS BY DEPTH --> NUM TRANS \n";
+    for (depth, count) in widths.iter() {
+        output += format!("- {:} --> {:} trans\n", depth, count).as_str();
+    }
+    output
+}
+fn format_ranges(title: &str, unit: &str, ranges: &[(u32, u64)]) -> String {
+    let mut output = String::new();
+    output += format!("> {:} \n", title).as_str();
+    for (range, count) in ranges.iter() {
         output += format!(
-            "- {:}:{:} --> {:} trans\n",
+            "- {:}:{:} --> {:}{:}\n",
             range,
             range + statistics::TIMESTAMP_RANGE,
-            count
+            count,
+            unit
         )
         .as_str();
     }
     output
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let path_file = args.get(1);
-    if path_file.is_none() {
-        eprintln!("Command needs an argument");
-        return ();
+// Bar charts of the transactions per depth and per timestamp range.
+fn display_charts(stats: &dto::Statistics, options: &TerminalOptions) {
+    let depths = stats
+        .transactions_by_depth
+        .iter()
+        .map(|(depth, count)| (depth.to_string(), *count))
+        .collect::<Vec<(String, u64)>>();
+    let ranges = stats
+        .range_timestamps
+        .iter()
+        .map(|(range, count)| {
+            (
+                format!("{:}:{:}", range, range + statistics::TIMESTAMP_RANGE),
+                *count,
+            )
+        })
+        .collect::<Vec<(String, u64)>>();
+    print!(
+        "{:}{:}",
+        terminal::bar_chart("DEPTH --> NUM TRANS", &depths, options),
+        terminal::bar_chart("TIMESTAMPS --> NUM TRANS", &ranges, options)
+    );
+}
+
+// Write `content` in the file `path`, `-` is the standard output.
+fn write_output(path: &str, content: &str) -> Result<(), Failure> {
+    if path == "-" {
+        print!("{:}", content);
+        return Ok(());
     }
-    let repo = DBRepository::new(&path_file.unwrap());
-    if repo.is_none() {
-        eprintln!("The path file must be correct");
-        return ();
+    fs::write(path, content)
+        .map_err(|e| Failure::Output(format!("The file {:} could not be written: {:}", path, e)))
+}
+
+// Load the database in `path` counting duplicated parents following the
+// command line policy.
+fn load(path: &str, cli: &Cli) -> Result<Graph, Failure> {
+    let repo = DBRepository::new(path)
+        .ok_or_else(|| Failure::MissingFile(format!("The file {:} does not exist", path)))?;
+    Ok(repo.load_with_policy(cli.policy)?)
+}
+
+// Groups of transaction ids of the file at `path`.
+fn load_groups(path: &str) -> Result<Vec<Vec<u32>>, Failure> {
+    infra::load_groups(path).map_err(|e| match e {
+        infra::InfraError::NotFileSpecified => {
+            Failure::MissingFile(format!("The file {:} does not exist", path))
+        }
+        _ => Failure::Parse(format!("The file {:} could not be parsed: {:}", path, e)),
+    })
+}
+
+// Content of graph `graph` in the format `to`.
+fn render(graph: &Graph, to: Target, options: &dot::DotOptions) -> Result<String, Failure> {
+    Ok(match to {
+        Target::Database => infra::to_database(graph),
+        Target::Graphml => graphml::export(graph),
+        Target::Gexf => gexf::export(graph),
+        Target::Dot => dot::export(graph, options)?,
+    })
+}
+
+// Signed difference of `right` against `left`.
+fn change(left: u64, right: u64) -> String {
+    format!("{:+}", right as i128 - left as i128)
+}
+
+// Changes of two histograms per key, keys missing in one side count as 0.
+fn histogram_changes(left: &[(u32, u64)], right: &[(u32, u64)]) -> Vec<(u32, u64, u64)> {
+    let mut keys = left
+        .iter()
+        .chain(right.iter())
+        .map(|(key, _)| *key)
+        .collect::<Vec<u32>>();
+    keys.sort();
+    keys.dedup();
+    let count = |histogram: &[(u32, u64)], key| {
+        histogram
+            .iter()
+            .find(|(other, _)| *other == key)
+            .map_or(0, |(_, count)| *count)
+    };
+    keys.into_iter()
+        .map(|key| (key, count(left, key), count(right, key)))
+        .collect()
+}
+
+fn display_diff(left: &[dto::Entry], right: &[dto::Entry]) {
+    let mut output = String::new();
+    for (left_entry, right_entry) in left.iter().zip(right.iter()) {
+        let label = &left_entry.label;
+        output += match (&left_entry.value, &right_entry.value) {
+            (dto::Value::Float(a), dto::Value::Float(b)) => {
+                format!("> {:}: {:.2} -> {:.2} ({:+.2})\n", label, a, b, b - a)
+            }
+            (dto::Value::Count(a), dto::Value::Count(b)) => {
+                format!("> {:}: {:} -> {:} ({:})\n", label, a, b, change(*a, *b))
+            }
+            (dto::Value::Transactions(a), dto::Value::Transactions(b)) => {
+                let (a, b) = (a.len() as u64, b.len() as u64);
+                format!("> {:}: {:} -> {:} ({:})\n", label, a, b, change(a, b))
+            }
+            (dto::Value::Transaction(a), dto::Value::Transaction(b)) => {
+                format!("> {:}: {:} -> {:}\n", label, a, b)
+            }
+            (dto::Value::ByDepth(a), dto::Value::ByDepth(b)) => {
+                let mut lines = format!("> {:} \n", label);
+                for (depth, a, b) in histogram_changes(a, b) {
+                    lines +=
+                        format!("- {:} --> {:} -> {:} ({:})\n", depth, a, b, change(a, b)).as_str();
+                }
+                lines
+            }
+            (dto::Value::ByTimeRange(a), dto::Value::ByTimeRange(b)) => {
+                let mut lines = format!("> {:} \n", label);
+                for (range, a, b) in histogram_changes(a, b) {
+                    lines += format!(
+                        "- {:}:{:} --> {:} -> {:} ({:})\n",
+                        range,
+                        range + statistics::TIMESTAMP_RANGE,
+                        a,
+                        b,
+                        change(a, b)
+                    )
+                    .as_str();
+                }
+                lines
+            }
+            _ => String::new(),
+        }
+        .as_str();
     }
+    print!("{:}", output);
+}
 
-    match repo.unwrap().load() {
-        Ok(model_graph) => {
-            let stats = statistics::stats(&model_graph);
-            display(&stats);
+// Identifiers `ids` separated by commas.
+fn join(ids: &[u32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn display_inspection(inspection: &dto::Inspection) {
+    let mut output = String::new();
+    output += format!("> TRANSACTION: {:}\n", inspection.id).as_str();
+    output += match inspection.parents {
+        Some((left, right)) => format!("> PARENTS: {:} (left), {:} (right)\n", left, right),
+        None => "> PARENTS: none (root)\n".to_string(),
+    }
+    .as_str();
+    output += format!("> TIMESTAMP: {:}\n", inspection.timestamp).as_str();
+    output += format!("> DEPTH: {:}\n", inspection.depth).as_str();
+    output += format!("> GENERATION: {:}\n", inspection.generation).as_str();
+    output += format!("> IN REF: {:}\n", inspection.in_reference).as_str();
+    output += format!("> APPROVERS: {:}\n", join(&inspection.approvers)).as_str();
+    output += format!("> CUMULATIVE WEIGHT: {:}\n", inspection.cumulative_weight).as_str();
+    output += format!("> PAST CONE: {:} trans\n", inspection.past_cone).as_str();
+    output += format!("> FUTURE CONE: {:} trans\n", inspection.future_cone).as_str();
+    output += format!("> TIP: {:}\n", if inspection.tip { "yes" } else { "no" }).as_str();
+    print!("{:}", output);
+}
+
+fn display_paths(paths: &dto::Paths, all: bool) {
+    let mut output = String::new();
+    match &paths.shortest {
+        Some(shortest) => {
+            let ids: Vec<String> = shortest.iter().map(|id| id.to_string()).collect();
+            output += format!("> SHORTEST PATH: {:}\n", ids.join(" -> ")).as_str();
+            output += format!("> LENGTH: {:} refs\n", shortest.len() - 1).as_str();
         }
-        Err(e) => {
-            let err_mesg = format!("The graph could not be loaded: {:?}", e);
-            eprintln!("{:}", err_mesg);
+        None => {
+            output += format!(
+                "> SHORTEST PATH: none, {:} and {:} are not in each other's cones\n",
+                paths.from, paths.to
+            )
+            .as_str();
+        }
+    }
+    output += format!("> PATHS: {:}\n", paths.count).as_str();
+    if all {
+        for path in paths.paths.iter() {
+            let ids: Vec<String> = path.iter().map(|id| id.to_string()).collect();
+            output += format!("{:}\n", ids.join(" -> ")).as_str();
+        }
+    }
+    print!("{:}", output);
+}
+
+fn display_ancestors(ancestors: &[dto::Ancestor]) {
+    let mut output = format!("> LOWEST COMMON ANCESTORS: {:}\n", ancestors.len());
+    for ancestor in ancestors.iter() {
+        output += format!(
+            "{:} (depth {:}, timestamp {:})\n",
+            ancestor.id, ancestor.depth, ancestor.timestamp
+        )
+        .as_str();
+    }
+    print!("{:}", output);
+}
+
+fn display_confirmations(confirmations: &dto::Confirmations) {
+    let percentage = |confirmed: u64, total: u64| confirmed as f64 * 100.0 / total.max(1) as f64;
+    let mut output = String::new();
+    output += format!("> RULE: {:}\n", confirmations.rule).as_str();
+    output += format!(
+        "> CONFIRMED: {:} of {:} trans ({:.2}%)\n",
+        confirmations.confirmed,
+        confirmations.total,
+        confirmations.rate * 100.0
+    )
+    .as_str();
+    output += "> CONFIRMED BY TIMESTAMP RANGE \n";
+    for (range, confirmed, total) in confirmations.by_time_range.iter() {
+        output += format!(
+            "- {:}:{:} --> {:} of {:} ({:.2}%)\n",
+            range,
+            range + statistics::TIMESTAMP_RANGE,
+            confirmed,
+            total,
+            percentage(*confirmed, *total)
+        )
+        .as_str();
+    }
+    for confirmation in confirmations.transactions.iter() {
+        output += format!(
+            "{:} {:} (weight {:}, coverage {:.2}%)\n",
+            confirmation.id,
+            if confirmation.confirmed {
+                "confirmed"
+            } else {
+                "unconfirmed"
+            },
+            confirmation.cumulative_weight,
+            confirmation.coverage
+        )
+        .as_str();
+    }
+    print!("{:}", output);
+}
+
+fn display_milestones(milestones: &dto::Milestones) {
+    let mut output = String::new();
+    output += format!("> MILESTONES: {:}\n", milestones.milestones.len()).as_str();
+    output += format!(
+        "> AVG MILESTONE INTERVAL: {:.2}\n",
+        milestones.average_interval
+    )
+    .as_str();
+    output += format!(
+        "> TRANS CONFIRMED: {:} ({:} unconfirmed)\n",
+        milestones.confirmed, milestones.unconfirmed
+    )
+    .as_str();
+    output += format!("> AVG LATENCY: {:.2}\n", milestones.average_latency).as_str();
+    output += format!("> MAX LATENCY: {:}\n", milestones.max_latency).as_str();
+    output += "> MILESTONE --> TRANS CONFIRMED \n";
+    for milestone in milestones.milestones.iter() {
+        output += format!(
+            "- {:} --> {:} (timestamp {:}, interval {:})\n",
+            milestone.id,
+            milestone.confirmed,
+            milestone.timestamp,
+            milestone
+                .interval
+                .map_or("-".to_string(), |interval| interval.to_string())
+        )
+        .as_str();
+    }
+    output += format_ranges("LATENCY --> NUM TRANS", " trans", &milestones.latencies).as_str();
+    for confirmation in milestones.transactions.iter() {
+        output += match (confirmation.milestone, confirmation.latency) {
+            (Some(milestone), Some(latency)) => format!(
+                "{:} milestone {:} (latency {:})\n",
+                confirmation.id, milestone, latency
+            ),
+            _ => format!("{:} unconfirmed\n", confirmation.id),
+        }
+        .as_str();
+    }
+    print!("{:}", output);
+}
+
+fn display_conflicts(conflicts: &dto::Conflicts) {
+    let mut output = String::new();
+    output += format!("> CONFLICT SETS: {:}\n", conflicts.conflicts.len()).as_str();
+    output += "> CONFLICT --> WINNER \n";
+    for conflict in conflicts.conflicts.iter() {
+        let weights = conflict
+            .transactions
+            .iter()
+            .map(|(id, weight)| format!("{:} (weight {:})", id, weight))
+            .collect::<Vec<String>>();
+        output += format!(
+            "- {:} --> {:}, {:} trans invalidated\n",
+            weights.join(", "),
+            conflict
+                .winner
+                .map_or("none".to_string(), |winner| winner.to_string()),
+            conflict.invalidated
+        )
+        .as_str();
+    }
+    output += format_transactions("TRANS INCONSISTENT", &conflicts.inconsistent).as_str();
+    output += format!("> SUBTANGLES INVALIDATED: {:}\n", conflicts.subtangles).as_str();
+    output += format_transactions("TRANS INVALIDATED", &conflicts.invalidated).as_str();
+    output += format!(
+        "> INVALIDATED RATE: {:.2}\n",
+        conflicts.invalidated.len() as f64 / conflicts.total.max(1) as f64
+    )
+    .as_str();
+    print!("{:}", output);
+}
+
+fn display_simulation(simulation: &Simulation) {
+    let mut output = String::new();
+    output += format!("> WALKS: {:}\n", simulation.walks).as_str();
+    output += format!("> AVG WALK LENGTH: {:.2}\n", simulation.average_length).as_str();
+    output += format!("> ORPHAN RATE: {:.2}\n", simulation.orphan_rate).as_str();
+    output += "> TIP --> TIMES SELECTED \n";
+    for (tip, count) in simulation.selected.iter() {
+        output += format!(
+            "- {:} --> {:} ({:.1}%)\n",
+            tip,
+            count,
+            *count as f64 * 100.0 / simulation.walks.max(1) as f64
+        )
+        .as_str();
+    }
+    output += "> WALK LENGTH --> NUM WALKS \n";
+    for (length, count) in simulation.walk_lengths.iter() {
+        output += format!("- {:} --> {:} walks\n", length, count).as_str();
+    }
+    print!("{:}", output);
+}
+
+// Identifiers of the transactions of graph `graph` selected by `query`,
+// sorted by id.
+fn select(graph: &Graph, query: &Query) -> Result<Vec<u32>, Failure> {
+    let known = |id: &u32| {
+        if graph.nodes.contains_key(id) {
+            Ok(*id)
+        } else {
+            Err(Failure::Usage(format!("unknown transaction id=`{:}`", id)))
+        }
+    };
+    let mut ids = match query {
+        Query::Tips => graph.tips(),
+        Query::Approvers(id) => graph.approvers(known(id)?).to_vec(),
+        Query::PastCone(id) => graph.past_cone(known(id)?).into_iter().collect(),
+        Query::FutureCone(id) => graph.future_cone(known(id)?).into_iter().collect(),
+        Query::Filter(filter) => query::select(graph, filter),
+    };
+    ids.sort();
+    Ok(ids)
+}
+
+// Registry with the built-in and the liveness statistics.
+fn registry(cli: &Cli) -> statistics::Registry {
+    let mut registry = statistics::Registry::with_builtins();
+    registry.set_threads(cli.threads);
+    liveness::register(&mut registry, cli.thresholds.clone())
+        .expect("liveness statistic names are unique");
+    registry
+}
+
+fn run(cli: Cli) -> Result<(), Failure> {
+    let registry = registry(&cli);
+    // detected only by the commands drawing in the terminal
+    let terminal_options = || TerminalOptions {
+        unicode: !cli.ascii,
+        ..TerminalOptions::detect()
+    };
+    match &cli.command {
+        Command::Help(usage) => print!("{:}", usage),
+        Command::ListStatistics => registry
+            .names()
+            .iter()
+            .for_each(|name| println!("{:}", name)),
+        Command::Stats {
+            path,
+            statistics,
+            format,
+            chart,
+        } if statistics.is_empty() => {
+            let graph = load(path, &cli)?;
+            let stats = statistics::stats_parallel(&graph, cli.threads);
+            let custom = registry.compute_custom(&graph);
+            match format {
+                Format::Text => {
+                    display(&stats);
+                    display_entries(&custom);
+                    if *chart {
+                        display_charts(&stats, &terminal_options());
+                    }
+                }
+                Format::Json => println!("{:}", json::statistics_with(&stats, &custom)),
+                Format::Csv => print!("{:}", csv::statistics_with(&stats, &custom)),
+                Format::Prometheus => print!("{:}", prometheus::statistics_with(&stats, &custom)),
+            }
+        }
+        Command::Stats {
+            path,
+            statistics,
+            format,
+            ..
+        } => {
+            let graph = load(path, &cli)?;
+            let entries = registry.compute(&graph, statistics)?;
+            match format {
+                Format::Text => display_entries(&entries),
+                Format::Json => println!("{:}", json::entries(&entries)),
+                Format::Csv => print!("{:}", csv::entries(&entries)),
+                Format::Prometheus => print!("{:}", prometheus::entries(&entries)),
+            }
+        }
+        Command::Validate { path } => {
+            let graph = load(path, &cli)?;
+            println!(
+                "> VALID: {:} transactions, {:} tips",
+                graph.nodes.len(),
+                graph.tips().len()
+            );
+        }
+        Command::Convert { input, output, to } => {
+            let graph = if input.ends_with(".graphml") {
+                let content = fs::read_to_string(input).map_err(|e| {
+                    Failure::MissingFile(format!("The file {:} could not be read: {:}", input, e))
+                })?;
+                graphml::import(&content)?
+            } else {
+                load(input, &cli)?
+            };
+            write_output(output, &render(&graph, *to, &Default::default())?)?;
+        }
+        Command::Export {
+            path,
+            to,
+            output,
+            dot,
+        } => {
+            let graph = load(path, &cli)?;
+            write_output(output, &render(&graph, *to, dot)?)?;
+        }
+        Command::Query {
+            path,
+            query,
+            summary,
+            format,
+        } => {
+            let graph = load(path, &cli)?;
+            let ids = select(&graph, query)?;
+            if *summary {
+                let selected = ids.iter().copied().collect::<HashSet<u32>>();
+                let stats = statistics::stats_matching(&graph, |node| selected.contains(&node.id));
+                match format {
+                    Format::Json => println!("{:}", json::statistics(&stats)),
+                    _ => display(&stats),
+                }
+                return Ok(());
+            }
+            match format {
+                Format::Json => println!(
+                    "{:}",
+                    Json::object([
+                        ("schema_version", Json::Unsigned(json::SCHEMA_VERSION)),
+                        (
+                            "transactions",
+                            Json::Array(ids.iter().map(|id| Json::Unsigned(*id as u64)).collect()),
+                        ),
+                    ])
+                ),
+                _ => ids.iter().for_each(|id| println!("{:}", id)),
+            }
+        }
+        Command::Inspect { path, id, format } => {
+            let graph = load(path, &cli)?;
+            let inspection = inspection::inspect(&graph, *id)?;
+            match format {
+                Format::Json => println!("{:}", json::inspection(&inspection)),
+                _ => display_inspection(&inspection),
+            }
+        }
+        Command::Path {
+            path,
+            from,
+            to,
+            all,
+            limit,
+            format,
+        } => {
+            let graph = load(path, &cli)?;
+            let limit = if *all { *limit } else { 0 };
+            let paths = inspection::paths(&graph, *from, *to, limit)?;
+            match format {
+                Format::Json => println!("{:}", json::paths(&paths)),
+                _ => display_paths(&paths, *all),
+            }
+        }
+        Command::Lca { path, a, b, format } => {
+            let graph = load(path, &cli)?;
+            let ancestors = inspection::common_ancestors(&graph, *a, *b)?;
+            match format {
+                Format::Json => println!("{:}", json::common_ancestors(*a, *b, &ancestors)),
+                _ => display_ancestors(&ancestors),
+            }
+        }
+        Command::Confirm {
+            path,
+            rule,
+            id,
+            list,
+            format,
+        } => {
+            let graph = load(path, &cli)?;
+            if let Some(id) = id.filter(|id| !graph.nodes.contains_key(id)) {
+                return Err(inspection::InspectionError::UnknownTransaction(id).into());
+            }
+            let mut confirmations = confirmation::confirmations(&graph, rule);
+            confirmations
+                .transactions
+                .retain(|confirmation| *list || Some(confirmation.id) == *id);
+            match format {
+                Format::Json => println!("{:}", json::confirmations(&confirmations)),
+                _ => display_confirmations(&confirmations),
+            }
+        }
+        Command::Milestones {
+            path,
+            milestones,
+            list,
+            format,
+        } => {
+            let graph = load(path, &cli)?;
+            let ids = match milestones {
+                Milestones::File(file) => load_groups(file)?.concat(),
+                Milestones::Every(period) => milestones::periodic(&graph, *period)?,
+                Milestones::Filter(filter) => query::select(&graph, filter),
+            };
+            let mut confirmations = milestones::confirmations(&graph, &ids)?;
+            if !list {
+                confirmations.transactions.clear();
+            }
+            match format {
+                Format::Json => println!("{:}", json::milestones(&confirmations)),
+                _ => display_milestones(&confirmations),
+            }
+        }
+        Command::Conflicts { path, sets, format } => {
+            let graph = load(path, &cli)?;
+            let conflicts = conflicts::resolve(&graph, &load_groups(sets)?)?;
+            match format {
+                Format::Json => println!("{:}", json::conflicts(&conflicts)),
+                _ => display_conflicts(&conflicts),
+            }
+        }
+        Command::Diff {
+            left,
+            right,
+            statistics,
+            format,
+        } => {
+            let left_entries = registry.compute(&load(left, &cli)?, statistics)?;
+            let right_entries = registry.compute(&load(right, &cli)?, statistics)?;
+            match format {
+                Format::Json => println!("{:}", json::diff(&left_entries, &right_entries)),
+                _ => display_diff(&left_entries, &right_entries),
+            }
+        }
+        Command::Report { path, output } => {
+            let graph = load(path, &cli)?;
+            let stats = statistics::stats_parallel(&graph, cli.threads);
+            write_output(output, &report::html(path, &graph, &stats))?;
+        }
+        Command::Simulate {
+            path,
+            options,
+            format,
+        } => {
+            let simulation = simulation::simulate(&load(path, &cli)?, options)?;
+            match format {
+                Format::Json => println!("{:}", json::simulation(&simulation)),
+                _ => display_simulation(&simulation),
+            }
+        }
+        Command::Generate { options, output } => {
+            write_output(output, &infra::to_database(&generator::generate(options)?))?;
+        }
+        Command::Repl { path } => repl::run(path, &cli)?,
+        Command::Serve {
+            path,
+            address,
+            interval,
+        } => {
+            let server = Arc::new(Server::new(load(path, &cli)?));
+            let listener = TcpListener::bind(address).map_err(|e| {
+                Failure::Output(format!(
+                    "The address {:} could not be bound: {:}",
+                    address, e
+                ))
+            })?;
+            println!("> SERVING: http://{:}", address);
+            server::serve(listener, server, Duration::from_secs(*interval))
+                .map_err(|e| Failure::Output(format!("The server stopped: {:}", e)))?;
+        }
+        Command::Dag { path, around } => {
+            let graph = load(path, &cli)?;
+            print!("{:}", terminal::dag(&graph, *around, &terminal_options())?);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    match cli::parse(&args).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("{:}", failure);
+            failure.exit_code()
         }
     }
 }
@@ -0,0 +1,880 @@
+// Output module. It renders the statistics data transfer objects in
+// machine readable formats, the text format for humans is in the CLI.
+use std::str::FromStr;
+
+use crate::services::dto;
+
+/// Output formats of the statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Prometheus,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "prometheus" => Ok(Format::Prometheus),
+            _ => Err(format!("unknown format `{:}`", value)),
+        }
+    }
+}
+
+/// Named metric shared by the tabular formats: `name` (stable identifier),
+/// `help` (description) and its `value`.
+struct Metric<'a> {
+    name: &'a str,
+    help: &'a str,
+    value: dto::Value,
+}
+
+// Metrics of all the statistics `stats`, named as the JSON fields.
+fn metrics(stats: &dto::Statistics) -> Vec<Metric<'static>> {
+    let metric = |name, help, value| Metric { name, help, value };
+    vec![
+        metric(
+            "average_depth",
+            "Average depth of the transactions from the root.",
+            dto::Value::Float(stats.average_depth),
+        ),
+        metric(
+            "average_nodes_by_depth",
+            "Average number of transactions per depth, without depth 0.",
+            dto::Value::Float(stats.average_nodes_by_depth),
+        ),
+        metric(
+            "average_in_references",
+            "Average number of in references per transaction.",
+            dto::Value::Float(stats.average_in_references),
+        ),
+        metric(
+            "last_transaction",
+            "Id of the transaction with the last timestamp.",
+            dto::Value::Transaction(stats.last_transaction),
+        ),
+        metric(
+            "most_referenced_transaction",
+            "Id of the transaction with most in references.",
+            dto::Value::Transaction(stats.most_referenced_transaction),
+        ),
+        metric(
+            "same_parent_transactions",
+            "Transactions with the same left and right parent.",
+            dto::Value::Count(stats.same_parent_transactions),
+        ),
+        metric(
+            "distinct_parent_transactions",
+            "Transactions with distinct left and right parents.",
+            dto::Value::Count(stats.distinct_parent_transactions),
+        ),
+        metric(
+            "max_layer_width",
+            "Size of the widest generation layer.",
+            dto::Value::Count(stats.max_layer_width),
+        ),
+        metric(
+            "max_width",
+            "Size of the widest antichain.",
+            dto::Value::Count(stats.max_width),
+        ),
+        metric(
+            "range_timestamps",
+            "Transactions per timestamp range.",
+            dto::Value::ByTimeRange(stats.range_timestamps.clone()),
+        ),
+        metric(
+            "transactions_by_depth",
+            "Transactions per depth level.",
+            dto::Value::ByDepth(stats.transactions_by_depth.clone()),
+        ),
+        metric(
+            "width_over_time",
+            "Open tips at the end of every timestamp range.",
+            dto::Value::ByTimeRange(stats.width_over_time.clone()),
+        ),
+    ]
+}
+
+// Metrics of the selected statistics `entries`, named as in the registry.
+fn entry_metrics(entries: &[dto::Entry]) -> Vec<Metric<'_>> {
+    entries
+        .iter()
+        .map(|entry| Metric {
+            name: &entry.name,
+            help: &entry.label,
+            value: entry.value.clone(),
+        })
+        .collect()
+}
+
+// Bounds `[from, to)` of the timestamp range `range`.
+fn range_bounds(range: u32) -> (u64, u64) {
+    let size = crate::services::statistics::TIMESTAMP_RANGE as u64;
+    (range as u64 * size, (range as u64 + 1) * size)
+}
+
+/// JSON rendering. The document schema is versioned with `SCHEMA_VERSION`,
+/// field names are stable within the same version.
+pub mod json {
+    use std::fmt;
+
+    use crate::services::dto;
+    use crate::services::statistics::TIMESTAMP_RANGE;
+    use crate::simulation::Simulation;
+
+    /// Version of the JSON documents, increased on breaking changes.
+    pub const SCHEMA_VERSION: u64 = 1;
+
+    /// JSON value. Objects keep the insertion order of their fields.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Json {
+        Null,
+        Bool(bool),
+        Unsigned(u64),
+        Float(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+
+    impl Json {
+        /// Object from `(field, value)` pairs.
+        pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+            Json::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            )
+        }
+    }
+
+    fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in value.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{:}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+
+    impl fmt::Display for Json {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Json::Null => write!(f, "null"),
+                Json::Bool(value) => write!(f, "{:}", value),
+                Json::Unsigned(value) => write!(f, "{:}", value),
+                // JSON has no representation for NaN or infinite numbers.
+                Json::Float(value) if !value.is_finite() => write!(f, "null"),
+                Json::Float(value) => write!(f, "{:?}", value),
+                Json::String(value) => write_string(f, value),
+                Json::Array(values) => {
+                    write!(f, "[")?;
+                    for (index, value) in values.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{:}", value)?;
+                    }
+                    write!(f, "]")
+                }
+                Json::Object(fields) => {
+                    write!(f, "{{")?;
+                    for (index, (name, value)) in fields.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ",")?;
+                        }
+                        write_string(f, name)?;
+                        write!(f, ":{:}", value)?;
+                    }
+                    write!(f, "}}")
+                }
+            }
+        }
+    }
+
+    fn by_depth(widths: &[(u32, u64)]) -> Json {
+        Json::Array(
+            widths
+                .iter()
+                .map(|&(depth, count)| {
+                    Json::object([
+                        ("depth", Json::Unsigned(depth as u64)),
+                        ("count", Json::Unsigned(count)),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    fn by_time_range(ranges: &[(u32, u64)]) -> Json {
+        Json::Array(
+            ranges
+                .iter()
+                .map(|&(range, count)| {
+                    Json::object([
+                        ("range", Json::Unsigned(range as u64)),
+                        (
+                            "from",
+                            Json::Unsigned(range as u64 * TIMESTAMP_RANGE as u64),
+                        ),
+                        (
+                            "to",
+                            Json::Unsigned((range as u64 + 1) * TIMESTAMP_RANGE as u64),
+                        ),
+                        ("count", Json::Unsigned(count)),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    /// JSON value of a single statistic result `value`.
+    pub fn value(value: &dto::Value) -> Json {
+        match value {
+            dto::Value::Float(value) => Json::Float(*value),
+            dto::Value::Count(value) => Json::Unsigned(*value),
+            dto::Value::Transaction(id) => Json::Unsigned(*id as u64),
+            dto::Value::ByDepth(widths) => by_depth(widths),
+            dto::Value::ByTimeRange(ranges) => by_time_range(ranges),
+            dto::Value::Transactions(ids) => {
+                Json::Array(ids.iter().map(|id| Json::Unsigned(*id as u64)).collect())
+            }
+        }
+    }
+
+    /// JSON document with all the statistics `stats`.
+    pub fn statistics(stats: &dto::Statistics) -> Json {
+        statistics_with(stats, &[])
+    }
+
+    /// JSON document with all the statistics `stats` followed by the
+    /// `custom` ones, named as in the registry.
+    pub fn statistics_with(stats: &dto::Statistics, custom: &[dto::Entry]) -> Json {
+        let mut document = Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("timestamp_range", Json::Unsigned(TIMESTAMP_RANGE as u64)),
+            ("average_depth", Json::Float(stats.average_depth)),
+            (
+                "average_nodes_by_depth",
+                Json::Float(stats.average_nodes_by_depth),
+            ),
+            (
+                "average_in_references",
+                Json::Float(stats.average_in_references),
+            ),
+            (
+                "last_transaction",
+                Json::Unsigned(stats.last_transaction as u64),
+            ),
+            (
+                "most_referenced_transaction",
+                Json::Unsigned(stats.most_referenced_transaction as u64),
+            ),
+            (
+                "same_parent_transactions",
+                Json::Unsigned(stats.same_parent_transactions),
+            ),
+            (
+                "distinct_parent_transactions",
+                Json::Unsigned(stats.distinct_parent_transactions),
+            ),
+            ("max_layer_width", Json::Unsigned(stats.max_layer_width)),
+            ("max_width", Json::Unsigned(stats.max_width)),
+            ("range_timestamps", by_time_range(&stats.range_timestamps)),
+            (
+                "transactions_by_depth",
+                by_depth(&stats.transactions_by_depth),
+            ),
+            ("width_over_time", by_time_range(&stats.width_over_time)),
+        ]);
+        if let Json::Object(fields) = &mut document {
+            fields.extend(
+                custom
+                    .iter()
+                    .map(|entry| (entry.name.clone(), value(&entry.value))),
+            );
+        }
+        document
+    }
+
+    /// JSON document with the selected statistics `entries`.
+    pub fn entries(entries: &[dto::Entry]) -> Json {
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("timestamp_range", Json::Unsigned(TIMESTAMP_RANGE as u64)),
+            (
+                "statistics",
+                Json::Array(
+                    entries
+                        .iter()
+                        .map(|entry| {
+                            Json::object([
+                                ("name", Json::String(entry.name.clone())),
+                                ("label", Json::String(entry.label.clone())),
+                                ("value", value(&entry.value)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// JSON document with the details of a transaction `inspection`.
+    pub fn inspection(inspection: &dto::Inspection) -> Json {
+        let parents = match inspection.parents {
+            Some((left, right)) => Json::object([
+                ("left", Json::Unsigned(left as u64)),
+                ("right", Json::Unsigned(right as u64)),
+            ]),
+            None => Json::Null,
+        };
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("id", Json::Unsigned(inspection.id as u64)),
+            ("parents", parents),
+            ("timestamp", Json::Unsigned(inspection.timestamp as u64)),
+            ("depth", Json::Unsigned(inspection.depth as u64)),
+            ("generation", Json::Unsigned(inspection.generation as u64)),
+            (
+                "in_reference",
+                Json::Unsigned(inspection.in_reference as u64),
+            ),
+            (
+                "approvers",
+                Json::Array(
+                    inspection
+                        .approvers
+                        .iter()
+                        .map(|id| Json::Unsigned(*id as u64))
+                        .collect(),
+                ),
+            ),
+            (
+                "cumulative_weight",
+                Json::Unsigned(inspection.cumulative_weight),
+            ),
+            ("past_cone", Json::Unsigned(inspection.past_cone)),
+            ("future_cone", Json::Unsigned(inspection.future_cone)),
+            ("tip", Json::Bool(inspection.tip)),
+        ])
+    }
+
+    /// JSON document of the paths `paths` between two transactions.
+    pub fn paths(paths: &dto::Paths) -> Json {
+        let ids =
+            |path: &[u32]| Json::Array(path.iter().map(|id| Json::Unsigned(*id as u64)).collect());
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("from", Json::Unsigned(paths.from as u64)),
+            ("to", Json::Unsigned(paths.to as u64)),
+            (
+                "shortest",
+                paths.shortest.as_deref().map_or(Json::Null, ids),
+            ),
+            (
+                "length",
+                paths
+                    .shortest
+                    .as_ref()
+                    .map_or(Json::Null, |path| Json::Unsigned(path.len() as u64 - 1)),
+            ),
+            ("count", Json::Unsigned(paths.count)),
+            (
+                "paths",
+                Json::Array(paths.paths.iter().map(|path| ids(path)).collect()),
+            ),
+        ])
+    }
+
+    /// JSON document of the lowest common ancestors `ancestors` of
+    /// transactions `a` and `b`.
+    pub fn common_ancestors(a: u32, b: u32, ancestors: &[dto::Ancestor]) -> Json {
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("a", Json::Unsigned(a as u64)),
+            ("b", Json::Unsigned(b as u64)),
+            (
+                "ancestors",
+                Json::Array(
+                    ancestors
+                        .iter()
+                        .map(|ancestor| {
+                            Json::object([
+                                ("id", Json::Unsigned(ancestor.id as u64)),
+                                ("depth", Json::Unsigned(ancestor.depth as u64)),
+                                ("timestamp", Json::Unsigned(ancestor.timestamp as u64)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// JSON document of the confirmations `confirmations` under a rule.
+    pub fn confirmations(confirmations: &dto::Confirmations) -> Json {
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("rule", Json::String(confirmations.rule.clone())),
+            ("confirmed", Json::Unsigned(confirmations.confirmed)),
+            ("total", Json::Unsigned(confirmations.total)),
+            ("rate", Json::Float(confirmations.rate)),
+            (
+                "by_time_range",
+                Json::Array(
+                    confirmations
+                        .by_time_range
+                        .iter()
+                        .map(|&(range, confirmed, total)| {
+                            Json::object([
+                                ("range", Json::Unsigned(range as u64)),
+                                ("confirmed", Json::Unsigned(confirmed)),
+                                ("total", Json::Unsigned(total)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "transactions",
+                Json::Array(
+                    confirmations
+                        .transactions
+                        .iter()
+                        .map(|confirmation| {
+                            Json::object([
+                                ("id", Json::Unsigned(confirmation.id as u64)),
+                                (
+                                    "cumulative_weight",
+                                    Json::Unsigned(confirmation.cumulative_weight),
+                                ),
+                                ("coverage", Json::Float(confirmation.coverage)),
+                                ("confirmed", Json::Bool(confirmation.confirmed)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// JSON document of the milestone confirmations `milestones`.
+    pub fn milestones(milestones: &dto::Milestones) -> Json {
+        let optional = |value: Option<u32>| value.map_or(Json::Null, |v| Json::Unsigned(v as u64));
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            (
+                "milestones",
+                Json::Array(
+                    milestones
+                        .milestones
+                        .iter()
+                        .map(|milestone| {
+                            Json::object([
+                                ("id", Json::Unsigned(milestone.id as u64)),
+                                ("timestamp", Json::Unsigned(milestone.timestamp as u64)),
+                                ("interval", optional(milestone.interval)),
+                                ("confirmed", Json::Unsigned(milestone.confirmed)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("confirmed", Json::Unsigned(milestones.confirmed)),
+            ("unconfirmed", Json::Unsigned(milestones.unconfirmed)),
+            ("average_interval", Json::Float(milestones.average_interval)),
+            ("average_latency", Json::Float(milestones.average_latency)),
+            ("max_latency", Json::Unsigned(milestones.max_latency as u64)),
+            (
+                "latencies",
+                Json::Array(
+                    milestones
+                        .latencies
+                        .iter()
+                        .map(|&(range, count)| {
+                            Json::object([
+                                ("range", Json::Unsigned(range as u64)),
+                                ("count", Json::Unsigned(count)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "transactions",
+                Json::Array(
+                    milestones
+                        .transactions
+                        .iter()
+                        .map(|confirmation| {
+                            Json::object([
+                                ("id", Json::Unsigned(confirmation.id as u64)),
+                                ("milestone", optional(confirmation.milestone)),
+                                ("latency", optional(confirmation.latency)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// JSON document of the conflict resolution `conflicts`.
+    pub fn conflicts(conflicts: &dto::Conflicts) -> Json {
+        let ids =
+            |ids: &[u32]| Json::Array(ids.iter().map(|id| Json::Unsigned(*id as u64)).collect());
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            (
+                "conflicts",
+                Json::Array(
+                    conflicts
+                        .conflicts
+                        .iter()
+                        .map(|conflict| {
+                            Json::object([
+                                (
+                                    "transactions",
+                                    Json::Array(
+                                        conflict
+                                            .transactions
+                                            .iter()
+                                            .map(|&(id, weight)| {
+                                                Json::object([
+                                                    ("id", Json::Unsigned(id as u64)),
+                                                    ("cumulative_weight", Json::Unsigned(weight)),
+                                                ])
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                                (
+                                    "winner",
+                                    conflict
+                                        .winner
+                                        .map_or(Json::Null, |winner| Json::Unsigned(winner as u64)),
+                                ),
+                                ("invalidated", Json::Unsigned(conflict.invalidated)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("inconsistent", ids(&conflicts.inconsistent)),
+            ("subtangles", Json::Unsigned(conflicts.subtangles)),
+            ("invalidated", ids(&conflicts.invalidated)),
+            ("total", Json::Unsigned(conflicts.total)),
+        ])
+    }
+
+    /// JSON document of the tip selection simulation `simulation`.
+    pub fn simulation(simulation: &Simulation) -> Json {
+        let counts = |name: &str, pairs: &[(u32, u64)]| {
+            Json::Array(
+                pairs
+                    .iter()
+                    .map(|&(key, count)| {
+                        Json::object([
+                            (name, Json::Unsigned(key as u64)),
+                            ("count", Json::Unsigned(count)),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("walks", Json::Unsigned(simulation.walks)),
+            ("orphan_rate", Json::Float(simulation.orphan_rate)),
+            ("average_length", Json::Float(simulation.average_length)),
+            ("selected", counts("tip", &simulation.selected)),
+            ("walk_lengths", counts("length", &simulation.walk_lengths)),
+        ])
+    }
+
+    /// JSON document comparing the statistics `left` and `right` of two
+    /// ledgers, computed with the same selection.
+    pub fn diff(left: &[dto::Entry], right: &[dto::Entry]) -> Json {
+        Json::object([
+            ("schema_version", Json::Unsigned(SCHEMA_VERSION)),
+            ("timestamp_range", Json::Unsigned(TIMESTAMP_RANGE as u64)),
+            (
+                "statistics",
+                Json::Array(
+                    left.iter()
+                        .zip(right.iter())
+                        .map(|(left, right)| {
+                            Json::object([
+                                ("name", Json::String(left.name.clone())),
+                                ("label", Json::String(left.label.clone())),
+                                ("left", value(&left.value)),
+                                ("right", value(&right.value)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+/// CSV rendering. The scalar statistics are one `metric,value` row each and
+/// every histogram is a separate table, tables are separated by an empty line.
+pub mod csv {
+    use super::{metrics, range_bounds, Metric};
+    use crate::services::dto;
+
+    fn tables(metrics: &[Metric]) -> String {
+        let mut output = String::from("metric,value\n");
+        let mut histograms = String::new();
+        let mut lists = String::new();
+        for metric in metrics.iter() {
+            match &metric.value {
+                dto::Value::Float(value) => {
+                    output += format!("{:},{:?}\n", metric.name, value).as_str()
+                }
+                dto::Value::Count(value) => {
+                    output += format!("{:},{:}\n", metric.name, value).as_str()
+                }
+                dto::Value::Transaction(id) => {
+                    output += format!("{:},{:}\n", metric.name, id).as_str()
+                }
+                dto::Value::ByDepth(widths) => {
+                    histograms += "\nhistogram,depth,count\n";
+                    for (depth, count) in widths.iter() {
+                        histograms += format!("{:},{:},{:}\n", metric.name, depth, count).as_str();
+                    }
+                }
+                dto::Value::ByTimeRange(ranges) => {
+                    histograms += "\nhistogram,range,from,to,count\n";
+                    for &(range, count) in ranges.iter() {
+                        let (from, to) = range_bounds(range);
+                        histograms +=
+                            format!("{:},{:},{:},{:},{:}\n", metric.name, range, from, to, count)
+                                .as_str();
+                    }
+                }
+                dto::Value::Transactions(ids) => {
+                    output += format!("{:},{:}\n", metric.name, ids.len()).as_str();
+                    lists += "\nlist,id\n";
+                    for id in ids.iter() {
+                        lists += format!("{:},{:}\n", metric.name, id).as_str();
+                    }
+                }
+            }
+        }
+        output + histograms.as_str() + lists.as_str()
+    }
+
+    /// CSV tables with all the statistics `stats`.
+    pub fn statistics(stats: &dto::Statistics) -> String {
+        tables(&metrics(stats))
+    }
+
+    /// CSV tables with all the statistics `stats` and the `custom` ones.
+    pub fn statistics_with(stats: &dto::Statistics, custom: &[dto::Entry]) -> String {
+        let mut metrics = metrics(stats);
+        metrics.extend(super::entry_metrics(custom));
+        tables(&metrics)
+    }
+
+    /// CSV tables with the selected statistics `entries`.
+    pub fn entries(entries: &[dto::Entry]) -> String {
+        tables(&super::entry_metrics(entries))
+    }
+}
+
+/// Prometheus text exposition rendering. Every statistic is a gauge named
+/// `ledger_<name>`, histograms are labelled by `depth` or by the timestamp
+/// range bounds `from` and `to`. Transaction ids are not quantities, they
+/// are info series `ledger_<name>_info{id="<id>"} 1`.
+pub mod prometheus {
+    use super::{metrics, range_bounds, Metric};
+    use crate::services::dto;
+
+    const PREFIX: &str = "ledger_";
+
+    fn number(value: f64) -> String {
+        match value {
+            value if value.is_nan() => "NaN".to_string(),
+            value if value == f64::INFINITY => "+Inf".to_string(),
+            value if value == f64::NEG_INFINITY => "-Inf".to_string(),
+            value => format!("{:?}", value),
+        }
+    }
+
+    fn exposition(metrics: &[Metric]) -> String {
+        let mut output = String::new();
+        for metric in metrics.iter() {
+            let name = match metric.value {
+                dto::Value::Transaction(_) => format!("{:}{:}_info", PREFIX, metric.name),
+                _ => format!("{:}{:}", PREFIX, metric.name),
+            };
+            output += format!("# HELP {:} {:}\n", name, metric.help).as_str();
+            output += format!("# TYPE {:} gauge\n", name).as_str();
+            match &metric.value {
+                dto::Value::Float(value) => {
+                    output += format!("{:} {:}\n", name, number(*value)).as_str()
+                }
+                dto::Value::Count(value) => output += format!("{:} {:}\n", name, value).as_str(),
+                dto::Value::Transaction(id) => {
+                    output += format!("{:}{{id=\"{:}\"}} 1\n", name, id).as_str()
+                }
+                dto::Value::ByDepth(widths) => {
+                    for (depth, count) in widths.iter() {
+                        output +=
+                            format!("{:}{{depth=\"{:}\"}} {:}\n", name, depth, count).as_str();
+                    }
+                }
+                dto::Value::ByTimeRange(ranges) => {
+                    for &(range, count) in ranges.iter() {
+                        let (from, to) = range_bounds(range);
+                        output += format!(
+                            "{:}{{from=\"{:}\",to=\"{:}\"}} {:}\n",
+                            name, from, to, count
+                        )
+                        .as_str();
+                    }
+                }
+                dto::Value::Transactions(ids) => {
+                    output += format!("{:} {:}\n", name, ids.len()).as_str()
+                }
+            }
+        }
+        output
+    }
+
+    /// Prometheus metrics of all the statistics `stats`.
+    pub fn statistics(stats: &dto::Statistics) -> String {
+        exposition(&metrics(stats))
+    }
+
+    /// Prometheus metrics of all the statistics `stats` and the `custom` ones.
+    pub fn statistics_with(stats: &dto::Statistics, custom: &[dto::Entry]) -> String {
+        let mut metrics = metrics(stats);
+        metrics.extend(super::entry_metrics(custom));
+        exposition(&metrics)
+    }
+
+    /// Prometheus metrics of the selected statistics `entries`.
+    pub fn entries(entries: &[dto::Entry]) -> String {
+        exposition(&super::entry_metrics(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json::*;
+    use super::{csv, prometheus};
+    use crate::graph::Graph;
+    use crate::services::{dto, statistics};
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    #[test]
+    fn should_render_json_values() {
+        let value = Json::object([
+            ("text", Json::String("a \"b\"\n".to_string())),
+            ("list", Json::Array(vec![Json::Unsigned(1), Json::Null])),
+            ("nan", Json::Float(f64::NAN)),
+            ("float", Json::Float(2.0)),
+        ]);
+        assert_eq!(
+            r#"{"text":"a \"b\"\n","list":[1,null],"nan":null,"float":2.0}"#,
+            value.to_string()
+        );
+    }
+
+    #[test]
+    fn should_render_statistics_as_json() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = statistics(&statistics::stats(&graph)).to_string();
+        assert!(output.starts_with(r#"{"schema_version":1,"timestamp_range":10,"#));
+        assert!(output.contains(r#""average_nodes_by_depth":2.5,"#));
+        assert!(output.contains(r#""range_timestamps":[{"range":0,"from":0,"to":10,"count":6}]"#));
+        let custom = vec![dto::Entry {
+            name: "pending".to_string(),
+            label: "TRANS PENDING".to_string(),
+            value: dto::Value::Transactions(vec![4, 6]),
+        }];
+        let output = statistics_with(&statistics::stats(&graph), &custom).to_string();
+        assert!(output.ends_with(r#""pending":[4,6]}"#));
+    }
+
+    #[test]
+    fn should_render_selected_entries_as_json() {
+        let selected = vec![dto::Entry {
+            name: "max_layer_width".to_string(),
+            label: "MAX LAYER WIDTH".to_string(),
+            value: dto::Value::Count(2),
+        }];
+        assert_eq!(
+            r#"{"schema_version":1,"timestamp_range":10,"statistics":[{"name":"max_layer_width","label":"MAX LAYER WIDTH","value":2}]}"#,
+            entries(&selected).to_string()
+        );
+    }
+
+    #[test]
+    fn should_render_diff_as_json() {
+        let entry = |value| dto::Entry {
+            name: "max_layer_width".to_string(),
+            label: "MAX LAYER WIDTH".to_string(),
+            value: dto::Value::Count(value),
+        };
+        assert_eq!(
+            r#"{"schema_version":1,"timestamp_range":10,"statistics":[{"name":"max_layer_width","label":"MAX LAYER WIDTH","left":2,"right":3}]}"#,
+            diff(&[entry(2)], &[entry(3)]).to_string()
+        );
+    }
+
+    #[test]
+    fn should_render_inspection_as_json() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = inspection(&crate::services::inspection::inspect(&graph, 1).unwrap());
+        assert_eq!(
+            r#"{"schema_version":1,"id":1,"parents":null,"timestamp":0,"depth":0,"generation":0,"in_reference":3,"approvers":[2,3],"cumulative_weight":6,"past_cone":0,"future_cone":5,"tip":false}"#,
+            output.to_string()
+        );
+    }
+
+    #[test]
+    fn should_render_statistics_as_csv() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = csv::statistics(&statistics::stats(&graph));
+        assert!(output.starts_with("metric,value\naverage_depth,1.3333333333333333\n"));
+        assert!(output.contains("\nmax_layer_width,2\n"));
+        assert!(output.contains("\nmax_width,2\n"));
+        assert!(output.contains("\n\nhistogram,range,from,to,count\nrange_timestamps,0,0,10,6\n"));
+        assert!(output.contains("\n\nhistogram,depth,count\ntransactions_by_depth,0,1\n"));
+    }
+
+    #[test]
+    fn should_render_statistics_as_prometheus() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = prometheus::statistics(&statistics::stats(&graph));
+        assert!(output.contains(
+            "# TYPE ledger_average_nodes_by_depth gauge\nledger_average_nodes_by_depth 2.5\n"
+        ));
+        assert!(output.contains("ledger_transactions_by_depth{depth=\"2\"} 3\n"));
+        assert!(output.contains("ledger_range_timestamps{from=\"0\",to=\"10\"} 6\n"));
+        assert!(output.contains(
+            "# TYPE ledger_last_transaction_info gauge\nledger_last_transaction_info{id=\"6\"} 1\n"
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+// Query module. It implements a small filter expression language over the
+// transaction fields and metrics, e.g.
+//
+//     depth > 5 and in_reference = 0 and timestamp between 100 and 200
+//
+// Expressions combine comparisons (`=`, `!=`, `<`, `<=`, `>`, `>=`), ranges
+// (`field between a and b`, inclusive) and flags (`tip`, `root`,
+// `same_parents`) with `and`, `or`, `not` and parentheses.
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::domain::Transaction;
+use crate::graph::Graph;
+
+/// Set of possible errors parsing a filter expression.
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("unexpected `{1}` at position {0}")]
+    UnexpectedToken(usize, String),
+    #[error("unexpected end of the expression")]
+    UnexpectedEnd,
+    #[error("unknown field `{0}`")]
+    UnknownField(String),
+    #[error("not correct number `{0}`")]
+    InvalidNumber(String),
+}
+
+/// Numeric fields of a transaction. `left` and `right` are the parents
+/// (the root has none) and `approvers` the number of approvers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Id,
+    Timestamp,
+    Depth,
+    Generation,
+    InReference,
+    Left,
+    Right,
+    Approvers,
+}
+
+/// Boolean fields of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flag {
+    Tip,
+    Root,
+    SameParents,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Syntax tree of a filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Compare(Field, Comparison, u64),
+    Between(Field, u64, u64),
+    Flag(Flag),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Field {
+    fn value(self, graph: &Graph, node: &Transaction) -> Option<u64> {
+        match self {
+            Field::Id => Some(node.id as u64),
+            Field::Timestamp => Some(node.timestamp as u64),
+            Field::Depth => Some(node.metrics.depth as u64),
+            Field::Generation => Some(node.metrics.generation as u64),
+            Field::InReference => Some(node.metrics.in_reference as u64),
+            Field::Left => node.parents.map(|(left, _)| left as u64),
+            Field::Right => node.parents.map(|(_, right)| right as u64),
+            Field::Approvers => Some(graph.approvers(node.id).len() as u64),
+        }
+    }
+}
+
+impl Comparison {
+    fn apply(self, left: u64, right: u64) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl Expr {
+    /// It checks if transaction `node` of graph `graph` matches the
+    /// expression. Comparisons over a missing parent do not match.
+    pub fn matches(&self, graph: &Graph, node: &Transaction) -> bool {
+        match self {
+            Expr::Compare(field, comparison, value) => field
+                .value(graph, node)
+                .is_some_and(|field| comparison.apply(field, *value)),
+            Expr::Between(field, from, to) => field
+                .value(graph, node)
+                .is_some_and(|field| (*from..=*to).contains(&field)),
+            Expr::Flag(Flag::Tip) => graph.is_tip(node.id),
+            Expr::Flag(Flag::Root) => node.parents.is_none(),
+            Expr::Flag(Flag::SameParents) => node.has_same_parents(),
+            Expr::Not(expr) => !expr.matches(graph, node),
+            Expr::And(left, right) => left.matches(graph, node) && right.matches(graph, node),
+            Expr::Or(left, right) => left.matches(graph, node) || right.matches(graph, node),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Word(text) | Token::Number(text) => text,
+            Token::Symbol(text) => text,
+        }
+    }
+}
+
+const SYMBOLS: [&str; 12] = [
+    "<=", ">=", "!=", "==", "&&", "||", "<", ">", "=", "!", "(", ")",
+];
+
+// Tokens of `input` with their positions.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < input.len() {
+        let rest = &input[position..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c.is_whitespace() {
+            position += c.len_utf8();
+        } else if c.is_ascii_digit() || c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let text = rest[..length].to_string();
+            tokens.push((
+                position,
+                if c.is_ascii_digit() {
+                    Token::Number(text)
+                } else {
+                    Token::Word(text.to_lowercase())
+                },
+            ));
+            position += length;
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push((position, Token::Symbol(symbol)));
+            position += symbol.len();
+        } else {
+            return Err(QueryError::UnexpectedToken(position, c.to_string()));
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent parser, from the lowest precedence:
+//
+//     or      := and (("or" | "||") and)*
+//     and     := not (("and" | "&&") not)*
+//     not     := ("not" | "!") not | primary
+//     primary := "(" or ")" | flag | field comparison number
+//              | field "between" number "and" number
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens
+            .get(self.position)
+            .map(|(_, token)| token.text())
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), QueryError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(QueryError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, alternatives: &[&str]) -> bool {
+        let found = self.peek().is_some_and(|text| alternatives.contains(&text));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), QueryError> {
+        match self.next()? {
+            (_, token) if token.text() == expected => Ok(()),
+            (position, token) => Err(QueryError::UnexpectedToken(
+                position,
+                token.text().to_string(),
+            )),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, QueryError> {
+        match self.next()? {
+            (_, Token::Number(text)) => text.parse().map_err(|_| QueryError::InvalidNumber(text)),
+            (position, token) => Err(QueryError::UnexpectedToken(
+                position,
+                token.text().to_string(),
+            )),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.accept(&["or", "||"]) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        while self.accept(&["and", "&&"]) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.accept(&["not", "!"]) {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let (position, token) = self.next()?;
+        let word = match token {
+            Token::Symbol("(") => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                return Ok(expr);
+            }
+            Token::Word(word) => word,
+            token => {
+                return Err(QueryError::UnexpectedToken(
+                    position,
+                    token.text().to_string(),
+                ))
+            }
+        };
+        let field = match word.as_str() {
+            "tip" => return Ok(Expr::Flag(Flag::Tip)),
+            "root" => return Ok(Expr::Flag(Flag::Root)),
+            "same_parents" => return Ok(Expr::Flag(Flag::SameParents)),
+            "id" => Field::Id,
+            "timestamp" => Field::Timestamp,
+            "depth" => Field::Depth,
+            "generation" => Field::Generation,
+            "in_reference" => Field::InReference,
+            "left" => Field::Left,
+            "right" => Field::Right,
+            "approvers" => Field::Approvers,
+            _ => return Err(QueryError::UnknownField(word)),
+        };
+        let (position, token) = self.next()?;
+        let comparison = match token.text() {
+            "between" => {
+                let from = self.number()?;
+                self.expect("and")?;
+                return Ok(Expr::Between(field, from, self.number()?));
+            }
+            "=" | "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            text => return Err(QueryError::UnexpectedToken(position, text.to_string())),
+        };
+        Ok(Expr::Compare(field, comparison, self.number()?))
+    }
+}
+
+impl FromStr for Expr {
+    type Err = QueryError;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            Some((position, token)) => Err(QueryError::UnexpectedToken(
+                *position,
+                token.text().to_string(),
+            )),
+            None => Ok(expr),
+        }
+    }
+}
+
+/// Identifiers of the transactions of graph `graph` matching `filter`,
+/// sorted by id.
+pub fn select(graph: &Graph, filter: &Expr) -> Vec<u32> {
+    let mut ids = graph
+        .nodes
+        .values()
+        .filter(|node| filter.matches(graph, node))
+        .map(|node| node.id)
+        .collect::<Vec<u32>>();
+    ids.sort();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    fn query(input: &str) -> Vec<u32> {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        select(&graph, &input.parse().unwrap())
+    }
+
+    #[test]
+    fn should_parse_with_precedence() {
+        assert_eq!(
+            Ok(Expr::Or(
+                Box::new(Expr::Flag(Flag::Tip)),
+                Box::new(Expr::And(
+                    Box::new(Expr::Compare(Field::Depth, Comparison::Greater, 1)),
+                    Box::new(Expr::Not(Box::new(Expr::Between(Field::Timestamp, 1, 2)))),
+                )),
+            )),
+            "tip or depth > 1 and not timestamp between 1 and 2".parse::<Expr>()
+        );
+    }
+
+    #[test]
+    fn should_select_matching_transactions() {
+        assert_eq!(vec![4, 5, 6], query("depth >= 2"));
+        assert_eq!(vec![5, 6], query("tip"));
+        assert_eq!(
+            vec![5],
+            query("DEPTH = 2 && in_reference == 0 && !(right = 4)")
+        );
+        assert_eq!(vec![4, 5], query("timestamp between 1 and 2"));
+        assert_eq!(vec![2, 3], query("left = 1 or root and id > 1"));
+        assert_eq!(vec![1, 2, 3], query("approvers = 2"));
+        assert_eq!(vec![2, 4, 5], query("same_parents"));
+    }
+
+    #[test]
+    fn should_fail_parsing_wrong_expressions() {
+        assert_eq!(
+            Err(QueryError::UnknownField("height".to_string())),
+            "height > 1".parse::<Expr>()
+        );
+        assert_eq!(
+            Err(QueryError::UnexpectedToken(8, "and".to_string())),
+            "depth > and".parse::<Expr>()
+        );
+        assert_eq!(Err(QueryError::UnexpectedEnd), "(tip".parse::<Expr>());
+        assert_eq!(
+            Err(QueryError::UnexpectedToken(4, "root".to_string())),
+            "tip root".parse::<Expr>()
+        );
+        assert_eq!(
+            Err(QueryError::UnexpectedToken(6, "$".to_string())),
+            "depth $ 1".parse::<Expr>()
+        );
+    }
+}
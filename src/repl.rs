@@ -0,0 +1,466 @@
+// REPL module. It loads the ledger once and answers commands over the
+// loaded graph until `exit`. In a terminal, lines are edited in raw mode
+// (set through termios once per session) with history (up/down arrows),
+// kept across sessions in a file, and tab completion of commands,
+// otherwise they are read as plain lines.
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use termios::{tcsetattr, Termios, ECHO, ICANON, ISIG, TCSANOW, VMIN, VTIME};
+
+use rust_challenge::domain::ParentPolicy;
+use rust_challenge::graph::Graph;
+use rust_challenge::infra::DBRepository;
+use rust_challenge::services::{inspection, statistics};
+
+use crate::cli::{Cli, Failure, Query, Target};
+
+const PROMPT: &str = "ledger> ";
+/// File of the history in the home directory.
+pub const HISTORY_FILE: &str = ".rust-challenge_history";
+/// Maximum number of commands loaded from the history file.
+pub const HISTORY_SIZE: usize = 1000;
+// Time to wait for the rest of an escape sequence before taking the ESC
+// key alone.
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+const COMMANDS: [&str; 13] = [
+    "help", "stats", "inspect", "cone", "path", "lca", "query", "summary", "export", "reload",
+    "history", "exit", "quit",
+];
+
+const HELP: &str = "Commands:
+  stats [name,...]                          Statistics of the ledger
+  inspect <id>                              Details of a transaction
+  cone past|future <id>                     Past or future cone of a transaction
+  path <from> <to>                          Shortest path and number of paths
+  lca <id> <id>                             Lowest common ancestors of two transactions
+  query <filter>                            Transactions matching a filter
+  summary <filter>                          Statistics of the transactions matching a filter
+  export dot|graphml|gexf|database <file|-> Export the DAG
+  reload                                    Load the ledger again
+  history                                   Previous commands
+  exit                                      Leave the shell
+";
+
+/// Candidates completing the last word of `line`: commands for the first
+/// word and the arguments of `cone` and `export` for the second one.
+pub fn complete(line: &str) -> Vec<&'static str> {
+    let words = line.split(' ').collect::<Vec<&str>>();
+    let (candidates, word): (&[&'static str], &str) = match words.as_slice() {
+        [word] => (&COMMANDS, word),
+        ["cone", word] => (&["past", "future"], word),
+        ["export", word] => (&["dot", "graphml", "gexf", "database"], word),
+        _ => return Vec::new(),
+    };
+    candidates
+        .iter()
+        .copied()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect()
+}
+
+// Longest common prefix of `words`.
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let first = words.first().copied().unwrap_or_default();
+    let length = words.iter().skip(1).fold(first.len(), |length, word| {
+        first
+            .bytes()
+            .zip(word.bytes())
+            .take(length)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    &first[..length]
+}
+
+/// Shell state: the ledger `path` loaded in `graph` and the `history` of
+/// commands.
+pub struct Session {
+    path: String,
+    policy: ParentPolicy,
+    threads: usize,
+    graph: Graph,
+    registry: statistics::Registry,
+    history: Vec<String>,
+}
+
+fn id(value: Option<&&str>) -> Result<u32, Failure> {
+    value
+        .ok_or_else(|| Failure::Usage("a transaction id is needed".to_string()))?
+        .parse()
+        .map_err(|_| Failure::Usage("not correct transaction id".to_string()))
+}
+
+impl Session {
+    /// Session over the ledger in `path` loaded with the command line
+    /// options `cli`.
+    pub fn new(path: &str, cli: &Cli) -> Result<Self, Failure> {
+        Ok(Session {
+            path: path.to_string(),
+            policy: cli.policy,
+            threads: cli.threads,
+            graph: crate::load(path, cli)?,
+            registry: crate::registry(cli),
+            history: Vec::new(),
+        })
+    }
+
+    /// Run the command `line`. It returns `false` when the shell must end.
+    pub fn execute(&mut self, line: &str) -> Result<bool, Failure> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(true);
+        }
+        self.history.push(line.to_string());
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let args = rest.split_whitespace().collect::<Vec<&str>>();
+        match command {
+            "help" => print!("{:}", HELP),
+            "exit" | "quit" => return Ok(false),
+            "history" => self
+                .history
+                .iter()
+                .enumerate()
+                .for_each(|(index, line)| println!("{:>4}  {:}", index + 1, line)),
+            "reload" => {
+                let repo = DBRepository::new(&self.path).ok_or_else(|| {
+                    Failure::MissingFile(format!("The file {:} does not exist", self.path))
+                })?;
+                self.graph = repo.load_with_policy(self.policy)?;
+                println!("> RELOADED: {:} transactions", self.graph.nodes.len());
+            }
+            "stats" if args.is_empty() => {
+                crate::display(&statistics::stats_parallel(&self.graph, self.threads));
+                crate::display_entries(&self.registry.compute_custom(&self.graph));
+            }
+            "stats" => {
+                let selection = args
+                    .join("")
+                    .split(',')
+                    .map(|name| name.to_string())
+                    .collect::<Vec<String>>();
+                crate::display_entries(&self.registry.compute(&self.graph, &selection)?);
+            }
+            "inspect" => {
+                crate::display_inspection(&inspection::inspect(&self.graph, id(args.first())?)?)
+            }
+            "cone" => {
+                let id = id(args.get(1))?;
+                let query = match args.first() {
+                    Some(&"past") => Query::PastCone(id),
+                    Some(&"future") => Query::FutureCone(id),
+                    _ => return Err(Failure::Usage("usage: cone past|future <id>".to_string())),
+                };
+                let ids = crate::select(&self.graph, &query)?;
+                println!("> CONE: {:} trans", ids.len());
+                println!("{:}", crate::join(&ids));
+            }
+            "path" => {
+                let (from, to) = (id(args.first())?, id(args.get(1))?);
+                crate::display_paths(&inspection::paths(&self.graph, from, to, 0)?, false);
+            }
+            "lca" => {
+                let (a, b) = (id(args.first())?, id(args.get(1))?);
+                crate::display_ancestors(&inspection::common_ancestors(&self.graph, a, b)?);
+            }
+            "query" | "summary" => {
+                let filter = rest
+                    .parse()
+                    .map_err(|e| Failure::Usage(format!("not correct filter: {:}", e)))?;
+                let ids = crate::select(&self.graph, &Query::Filter(filter))?;
+                if command == "query" {
+                    println!("> MATCHES: {:} trans", ids.len());
+                    println!("{:}", crate::join(&ids));
+                } else {
+                    let selected = ids.into_iter().collect::<HashSet<u32>>();
+                    crate::display(&statistics::stats_matching(&self.graph, |node| {
+                        selected.contains(&node.id)
+                    }));
+                }
+            }
+            "export" => {
+                let (Some(to), Some(output)) = (args.first(), args.get(1)) else {
+                    return Err(Failure::Usage(
+                        "usage: export dot|graphml|gexf|database <file|->".to_string(),
+                    ));
+                };
+                let to: Target = to.parse().map_err(Failure::Usage)?;
+                crate::write_output(
+                    output,
+                    &crate::render(&self.graph, to, &Default::default())?,
+                )?;
+            }
+            _ => {
+                return Err(Failure::Usage(format!(
+                    "unknown command `{:}`, type `help` for the commands",
+                    command
+                )))
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Terminal in raw mode while alive, the previous mode is restored on drop.
+// Output processing and signals are left alone, only the line discipline
+// and the echo are disabled.
+struct RawMode {
+    saved: Termios,
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        let descriptor = io::stdin().as_raw_fd();
+        let saved = Termios::from_fd(descriptor).ok()?;
+        let mut raw = saved;
+        raw.c_lflag &= !(ICANON | ECHO | ISIG);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        tcsetattr(descriptor, TCSANOW, &raw).ok()?;
+        Some(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = tcsetattr(io::stdin().as_raw_fd(), TCSANOW, &self.saved);
+    }
+}
+
+// Terminal in raw mode for the whole session, its bytes are read by a
+// thread so an escape sequence can be told apart from the ESC key alone.
+struct Terminal {
+    input: Receiver<u8>,
+    _raw: RawMode,
+}
+
+impl Terminal {
+    fn open() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        let raw = RawMode::enable()?;
+        let (sender, input) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in io::stdin().lock().bytes() {
+                let Ok(byte) = byte else {
+                    break;
+                };
+                if sender.send(byte).is_err() {
+                    break;
+                }
+            }
+        });
+        Some(Terminal { input, _raw: raw })
+    }
+}
+
+// Line editor in raw mode with history and tab completion. It returns
+// `None` at the end of the input (Ctrl-D on an empty line).
+fn edit_line(history: &[String], terminal: &Terminal) -> io::Result<Option<String>> {
+    let mut stdout = io::stdout().lock();
+    let mut line = String::new();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut position = history.len();
+    let redraw = |stdout: &mut io::StdoutLock, line: &str| -> io::Result<()> {
+        write!(stdout, "\r\x1b[K{:}{:}", PROMPT, line)?;
+        stdout.flush()
+    };
+    redraw(&mut stdout, &line)?;
+    loop {
+        let Ok(byte) = terminal.input.recv() else {
+            return Ok(None);
+        };
+        match byte {
+            b'\r' | b'\n' => {
+                write!(stdout, "\r\n")?;
+                return Ok(Some(line));
+            }
+            // Ctrl-C discards the line, Ctrl-D ends an empty one.
+            3 => {
+                write!(stdout, "^C\r\n")?;
+                line.clear();
+            }
+            4 if line.is_empty() => {
+                write!(stdout, "\r\n")?;
+                return Ok(None);
+            }
+            8 | 127 => {
+                line.pop();
+            }
+            b'\t' => {
+                let candidates = complete(&line);
+                let word = line.rsplit(' ').next().unwrap_or_default().len();
+                let prefix = common_prefix(&candidates);
+                if candidates.len() == 1 {
+                    line.truncate(line.len() - word);
+                    line += prefix;
+                    line.push(' ');
+                } else if prefix.len() > word {
+                    line.truncate(line.len() - word);
+                    line += prefix;
+                } else if !candidates.is_empty() {
+                    write!(stdout, "\r\n{:}\r\n", candidates.join("  "))?;
+                }
+            }
+            // Escape sequences, only the up and down arrows are handled and
+            // the ESC key alone is ignored.
+            27 => {
+                let mut sequence = [0u8; 2];
+                for byte in sequence.iter_mut() {
+                    match terminal.input.recv_timeout(ESCAPE_TIMEOUT) {
+                        Ok(next) => *byte = next,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return Ok(None),
+                    }
+                }
+                match sequence {
+                    [b'[', b'A'] if position > 0 => position -= 1,
+                    [b'[', b'B'] if position < history.len() => position += 1,
+                    _ => continue,
+                }
+                line = history.get(position).cloned().unwrap_or_default();
+            }
+            byte if byte >= 0x20 => {
+                pending.push(byte);
+                if let Ok(text) = std::str::from_utf8(&pending) {
+                    line += text;
+                    pending.clear();
+                } else if pending.len() >= 4 {
+                    pending.clear();
+                }
+            }
+            _ => {}
+        }
+        redraw(&mut stdout, &line)?;
+    }
+}
+
+// Next command line, `None` at the end of the input.
+fn read_line(history: &[String], terminal: Option<&Terminal>) -> io::Result<Option<String>> {
+    if let Some(terminal) = terminal {
+        return edit_line(history, terminal);
+    }
+    print!("{:}", PROMPT);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+/// Last `HISTORY_SIZE` commands of the history file `path`, empty when it
+/// does not exist.
+pub fn load_history(path: &Path) -> Vec<String> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let lines = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>();
+    lines[lines.len().saturating_sub(HISTORY_SIZE)..].to_vec()
+}
+
+/// Append the command `line` to the history file `path`.
+pub fn append_history(path: &Path, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{:}", line)
+}
+
+/// Run the shell over the ledger in `path` until `exit` or the end of the
+/// input. Failing commands print their error and the shell goes on.
+pub fn run(path: &str, cli: &Cli) -> Result<(), Failure> {
+    let mut session = Session::new(path, cli)?;
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+    if let Some(history) = &history {
+        session.history = load_history(history);
+    }
+    println!(
+        "> LOADED: {:} transactions, type `help` for the commands",
+        session.graph.nodes.len()
+    );
+    let terminal = Terminal::open();
+    loop {
+        let line = read_line(&session.history, terminal.as_ref())
+            .map_err(|e| Failure::Output(format!("The input could not be read: {:}", e)))?;
+        let Some(line) = line else {
+            return Ok(());
+        };
+        if let (Some(history), false) = (&history, line.trim().is_empty()) {
+            // a history that cannot be written is not worth ending the shell
+            let _ = append_history(history, line.trim());
+        }
+        match session.execute(&line) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(failure) => eprintln!("{:}", failure),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn should_complete_commands_and_arguments() {
+        assert_eq!(vec!["stats", "summary"], complete("s"));
+        assert_eq!(vec!["future"], complete("cone f"));
+        assert_eq!(vec!["graphml", "gexf"], complete("export g"));
+        assert!(complete("inspect 3").is_empty());
+        assert_eq!("g", common_prefix(&complete("export g")));
+        assert_eq!("s", common_prefix(&complete("s")));
+    }
+
+    #[test]
+    fn should_execute_commands_over_loaded_ledger() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("db.txt");
+        fs::write(&path, "5\n1 1 0\n1 2 0\n2 2 1\n3 3 2\n3 4 3\n").unwrap();
+        let cli = crate::cli::parse(&["rust-challenge".to_string(), "db.txt".to_string()]).unwrap();
+        let mut session = Session::new(path.to_str().unwrap(), &cli).unwrap();
+        assert_eq!(Ok(true), session.execute("inspect 3"));
+        assert_eq!(Ok(true), session.execute("query depth = 2 and tip"));
+        assert!(matches!(
+            session.execute("inspect 99"),
+            Err(Failure::Usage(_))
+        ));
+        assert!(matches!(
+            session.execute("cone sideways 3"),
+            Err(Failure::Usage(_))
+        ));
+        fs::write(&path, "1\n1 1 0\n").unwrap();
+        assert_eq!(Ok(true), session.execute("reload"));
+        assert_eq!(2, session.graph.nodes.len());
+        assert_eq!(5, session.history.len());
+        assert_eq!(Ok(false), session.execute("exit"));
+    }
+
+    #[test]
+    fn should_persist_history() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        assert!(load_history(&path).is_empty());
+        for line in (0..HISTORY_SIZE + 2).map(|index| format!("inspect {:}", index)) {
+            append_history(&path, &line).unwrap();
+        }
+        let history = load_history(&path);
+        assert_eq!(HISTORY_SIZE, history.len());
+        assert_eq!("inspect 2", history[0]);
+        assert_eq!(
+            format!("inspect {:}", HISTORY_SIZE + 1),
+            history[HISTORY_SIZE - 1]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+// Report module. It renders a self-contained HTML report of the ledger:
+// summary statistics, histograms as inline SVG charts and the most
+// referenced transactions, without any external asset.
+use crate::graph::Graph;
+use crate::services::dto;
+use crate::services::statistics::{self, TIMESTAMP_RANGE};
+
+/// Number of transactions listed in the top referenced table.
+pub const TOP_REFERENCED: usize = 10;
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 260.0;
+const MARGIN_LEFT: f64 = 50.0;
+const MARGIN_RIGHT: f64 = 10.0;
+const MARGIN_TOP: f64 = 20.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const MAX_AXIS_LABELS: usize = 20;
+const MAX_TIME_BUCKETS: u64 = 100;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+td,th{border:1px solid #ccc;padding:4px 8px}td{text-align:right}\
+th{text-align:left;background:#f4f4f4}\
+svg{display:block;margin-bottom:1.5em}\
+svg text{font-size:11px;fill:#444}.bar{fill:#4a78b5}.axis{stroke:#888}";
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Inline SVG bar chart of `bars` (label, count) titled `title`.
+fn bar_chart(title: &str, bars: &[(String, u64)]) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:}\" height=\"{:}\" role=\"img\" aria-label=\"{:}\">\n",
+        CHART_WIDTH,
+        CHART_HEIGHT,
+        escape(title)
+    );
+    let plot_width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let bottom = MARGIN_TOP + plot_height;
+    let max = bars
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    svg += format!(
+        "<line class=\"axis\" x1=\"{:}\" y1=\"{:}\" x2=\"{:}\" y2=\"{:}\"/>\n",
+        MARGIN_LEFT,
+        bottom,
+        CHART_WIDTH - MARGIN_RIGHT,
+        bottom
+    )
+    .as_str();
+    svg += format!(
+        "<text x=\"{:}\" y=\"{:}\" text-anchor=\"end\">{}</text>\n<text x=\"{:}\" y=\"{:}\" text-anchor=\"end\">0</text>\n",
+        MARGIN_LEFT - 6.0,
+        MARGIN_TOP + 4.0,
+        max,
+        MARGIN_LEFT - 6.0,
+        bottom
+    )
+    .as_str();
+    if bars.is_empty() {
+        return svg + "</svg>\n";
+    }
+    let slot = plot_width / bars.len() as f64;
+    let label_step = bars.len().div_ceil(MAX_AXIS_LABELS);
+    for (index, (label, count)) in bars.iter().enumerate() {
+        let height = plot_height * *count as f64 / max as f64;
+        let x = MARGIN_LEFT + slot * index as f64;
+        svg += format!(
+            "<rect class=\"bar\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{:}: {}</title></rect>\n",
+            x + slot * 0.1,
+            bottom - height,
+            slot * 0.8,
+            height,
+            escape(label),
+            count
+        )
+        .as_str();
+        if index % label_step == 0 {
+            svg += format!(
+                "<text x=\"{:.1}\" y=\"{:}\" text-anchor=\"middle\">{}</text>\n",
+                x + slot / 2.0,
+                bottom + 16.0,
+                escape(label)
+            )
+            .as_str();
+        }
+    }
+    svg + "</svg>\n"
+}
+
+// Histogram `pairs` sorted by key with a zero count for every missing key
+// between the first and the last one, so the gaps show in the charts. Only
+// for bounded keys (depths, in references), not for timestamp ranges.
+fn dense(pairs: &[(u32, u64)]) -> Vec<(u32, u64)> {
+    let (Some(&(first, _)), Some(&(last, _))) = (pairs.first(), pairs.last()) else {
+        return Vec::new();
+    };
+    let mut counts = pairs.iter().copied().peekable();
+    (first..=last)
+        .map(|key| match counts.next_if(|&(next, _)| next == key) {
+            Some(pair) => pair,
+            None => (key, 0),
+        })
+        .collect()
+}
+
+// Timestamp ranges `ranges` sorted by range merged in at most
+// `MAX_TIME_BUCKETS` buckets of consecutive ranges as `(from, to, count)`
+// timestamps, empty buckets included. Sparse timestamps (e.g. epoch seconds)
+// span many more ranges than transactions, they are not filled one by one.
+fn time_buckets(ranges: &[(u32, u64)]) -> Vec<(u64, u64, u64)> {
+    let (Some(&(first, _)), Some(&(last, _))) = (ranges.first(), ranges.last()) else {
+        return Vec::new();
+    };
+    let (first, span) = (first as u64, (last - first) as u64 + 1);
+    let per_bucket = span.div_ceil(MAX_TIME_BUCKETS);
+    let mut counts = vec![0; span.div_ceil(per_bucket) as usize];
+    for &(range, count) in ranges {
+        counts[((range as u64 - first) / per_bucket) as usize] += count;
+    }
+    let width = per_bucket * TIMESTAMP_RANGE as u64;
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(bucket, count)| {
+            let from = (first + bucket as u64 * per_bucket) * TIMESTAMP_RANGE as u64;
+            (from, from + width, count)
+        })
+        .collect()
+}
+
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut output = String::from("<table>\n<tr>");
+    for header in headers {
+        output += format!("<th>{}</th>", escape(header)).as_str();
+    }
+    output += "</tr>\n";
+    for row in rows {
+        output += "<tr>";
+        for (index, cell) in row.iter().enumerate() {
+            let tag = if index == 0 { "th" } else { "td" };
+            output += format!("<{}>{}</{}>", tag, escape(cell), tag).as_str();
+        }
+        output += "</tr>\n";
+    }
+    output + "</table>\n"
+}
+
+fn summary(stats: &dto::Statistics) -> Vec<Vec<String>> {
+    let row = |name: &str, value: String| vec![name.to_string(), value];
+    vec![
+        row("AVG DAG DEPTH", format!("{:.2}", stats.average_depth)),
+        row(
+            "AVG TXS PER DEPTH",
+            format!("{:.2}", stats.average_nodes_by_depth),
+        ),
+        row("AVG REF", format!("{:.2}", stats.average_in_references)),
+        row("TRANS LAST", stats.last_transaction.to_string()),
+        row(
+            "TRANS MOST IN REF",
+            stats.most_referenced_transaction.to_string(),
+        ),
+        row(
+            "TRANS SAME PARENTS",
+            stats.same_parent_transactions.to_string(),
+        ),
+        row(
+            "TRANS DISTINCT PARENTS",
+            stats.distinct_parent_transactions.to_string(),
+        ),
+        row("MAX LAYER WIDTH", stats.max_layer_width.to_string()),
+        row("MAX WIDTH", stats.max_width.to_string()),
+    ]
+}
+
+/// HTML report of graph `graph` with its statistics `stats`. The `source`
+/// (e.g. the database path) is shown in the heading.
+pub fn html(source: &str, graph: &Graph, stats: &dto::Statistics) -> String {
+    let depths = dense(&stats.transactions_by_depth)
+        .iter()
+        .map(|(depth, count)| (depth.to_string(), *count))
+        .collect::<Vec<(String, u64)>>();
+    let in_references = dense(&statistics::in_reference_distribution(graph))
+        .iter()
+        .map(|(in_reference, count)| (in_reference.to_string(), *count))
+        .collect::<Vec<(String, u64)>>();
+    let timestamps = time_buckets(&stats.range_timestamps)
+        .iter()
+        .map(|(from, to, count)| (format!("{:}-{:}", from, to), *count))
+        .collect::<Vec<(String, u64)>>();
+    let top_referenced = statistics::top_referenced(graph, TOP_REFERENCED)
+        .iter()
+        .map(|(id, in_reference)| {
+            let node = &graph.nodes[id];
+            vec![
+                id.to_string(),
+                in_reference.to_string(),
+                node.timestamp.to_string(),
+                node.metrics.depth.to_string(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    let mut output =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    output += "<title>Ledger statistics report</title>\n";
+    output += format!("<style>{}</style>\n</head>\n<body>\n", STYLE).as_str();
+    output += "<h1>Ledger statistics report</h1>\n";
+    output += format!(
+        "<p>{:} &mdash; {:} transactions</p>\n",
+        escape(source),
+        graph.nodes.len()
+    )
+    .as_str();
+    output += "<h2>Summary</h2>\n";
+    output += table(&["Statistic", "Value"], &summary(stats)).as_str();
+    output += "<h2>Transactions per depth</h2>\n";
+    output += bar_chart("Transactions per depth", &depths).as_str();
+    output += "<h2>In references distribution</h2>\n";
+    output += bar_chart("Transactions per number of in references", &in_references).as_str();
+    output += "<h2>Transactions per timestamp range</h2>\n";
+    output += bar_chart("Transactions per timestamp range", &timestamps).as_str();
+    output += "<h2>Top referenced transactions</h2>\n";
+    output += table(
+        &["Id", "In references", "Timestamp", "Depth"],
+        &top_referenced,
+    )
+    .as_str();
+    output += "</body>\n</html>\n";
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    #[test]
+    fn should_render_self_contained_report() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = html("db<1>.txt", &graph, &statistics::stats(&graph));
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.contains("<p>db&lt;1&gt;.txt &mdash; 6 transactions</p>"));
+        assert!(output.contains("<tr><th>AVG TXS PER DEPTH</th><td>2.50</td></tr>"));
+        assert!(output.contains("<tr><th>1</th><td>3</td><td>0</td><td>0</td></tr>"));
+        assert_eq!(3, output.matches("<svg ").count());
+        assert!(
+            !output.contains("<script") && !output.contains("<link") && !output.contains("src=")
+        );
+    }
+
+    #[test]
+    fn should_keep_empty_buckets() {
+        assert_eq!(
+            vec![(1, 2), (2, 0), (3, 0), (4, 1)],
+            dense(&[(1, 2), (4, 1)])
+        );
+        // no transaction between the timestamps 10 and 30
+        let graph = Graph::try_from(vec![(1, 1, 5), (2, 2, 35)]).unwrap();
+        let output = html("db.txt", &graph, &statistics::stats(&graph));
+        assert!(output.contains("height=\"0.0\"><title>10-20: 0</title>"));
+        assert!(output.contains("height=\"0.0\"><title>20-30: 0</title>"));
+    }
+
+    #[test]
+    fn should_merge_sparse_timestamp_ranges() {
+        // the root at 0 and two transactions at epoch seconds
+        let graph = Graph::try_from(vec![(1, 1, 1_700_000_000), (2, 2, 1_700_000_100)]).unwrap();
+        let buckets = time_buckets(&statistics::stats(&graph).range_timestamps);
+        assert_eq!(100, buckets.len());
+        // every bucket merges 1700001 ranges of 10
+        assert_eq!((0, 17_000_010, 1), buckets[0]);
+        assert_eq!((1_683_000_990, 1_700_001_000, 2), buckets[99]);
+        let output = html("db.txt", &graph, &statistics::stats(&graph));
+        assert!(output.contains("<title>1683000990-1700001000: 2</title>"));
+    }
+
+    #[test]
+    fn should_scale_bars_to_the_maximum() {
+        let bars = vec![("a".to_string(), 2), ("b".to_string(), 4)];
+        let svg = bar_chart("chart", &bars);
+        assert_eq!(2, svg.matches("<rect ").count());
+        assert!(svg.contains("height=\"200.0\"><title>b: 4</title>"));
+        assert!(svg.contains("height=\"100.0\"><title>a: 2</title>"));
+    }
+}
@@ -0,0 +1,767 @@
+// Server module. It exposes a ledger loaded once through a small HTTP/1.1
+// JSON API over the standard library `TcpListener`, one thread per
+// connection (up to `MAX_CONNECTIONS`) and one request per connection.
+//
+//     GET  /stats                          statistics
+//     GET  /tips                           tips
+//     GET  /transactions/{id}              transaction details
+//     GET  /transactions/{id}/past-cone    past cone
+//     GET  /transactions/{id}/future-cone  future cone
+//     GET  /path?from={id}&to={id}         shortest path and number of paths
+//     POST /transactions                   new transaction {"left", "right", "timestamp"}
+//     GET  /events                         server-sent events stream
+//
+// The events stream pushes every accepted transaction (`transaction`) with
+// its metrics, every rejected one (`rejected`) with the graph error and
+// periodic statistics snapshots (`stats`).
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::domain::Transaction;
+use crate::graph::Graph;
+use crate::output::json::{self, Json};
+use crate::services::{inspection, statistics};
+
+/// Maximum size of a request body.
+pub const MAX_BODY: usize = 64 * 1024;
+/// Maximum size of the request line and of each header line.
+pub const MAX_LINE: usize = 8 * 1024;
+/// Maximum number of headers of a request.
+pub const MAX_HEADERS: usize = 100;
+/// Time a connection may take to send its request or read a response.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+/// Events buffered for a subscriber, the ones lagging further behind are
+/// dropped.
+pub const EVENTS_BUFFER: usize = 1024;
+/// Interval of the comments keeping idle event streams alive.
+pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// Connections served at the same time, event streams included. Further
+/// ones are answered with a 503 error.
+pub const MAX_CONNECTIONS: usize = 256;
+/// Pause after a failed `accept` (e.g. out of file descriptors).
+pub const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Set of possible errors reading a request.
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("not correct request: {0}")]
+    BadRequest(String),
+    #[error("request headers too large: {0}")]
+    HeadersTooLarge(String),
+    #[error("connection error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// HTTP request: `method`, `path` without the query string, the `query`
+/// parameters and the `body`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// HTTP response with a JSON `body`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Request {
+    /// Request `method` on `target` (path and query string) with `body`.
+    pub fn new(method: &str, target: &str, body: &str) -> Self {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (decode(name), decode(value))
+                })
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Percent-decoded query component `text`, with `+` as a space. Malformed
+// escapes are kept as they are.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+            (b'+', None) => decoded.push(b' '),
+            (byte, None) => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl Response {
+    fn ok(body: Json) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            body: Json::object([("error", Json::String(message.to_string()))]),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// Write the response in `writer` as HTTP/1.1.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            writer,
+            "HTTP/1.1 {:} {:}\r\nContent-Type: application/json\r\nContent-Length: {:}\r\nConnection: close\r\n\r\n{:}",
+            self.status,
+            self.reason(),
+            body.len(),
+            body
+        )?;
+        writer.flush()
+    }
+}
+
+// Read a line of at most `MAX_LINE` bytes from `reader` into `line`,
+// returning its length or `None` when it is longer.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<Option<usize>> {
+    let length = reader.take(MAX_LINE as u64 + 1).read_line(line)?;
+    Ok((length <= MAX_LINE).then_some(length))
+}
+
+/// Read one HTTP/1.1 request from `reader`, rejecting request lines and
+/// headers longer than `MAX_LINE` and more than `MAX_HEADERS` headers.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, ServerError> {
+    let bad_request = |message: &str| ServerError::BadRequest(message.to_string());
+    let too_large = |message: &str| ServerError::HeadersTooLarge(message.to_string());
+    let mut line = String::new();
+    if read_line(reader, &mut line)?.is_none() {
+        return Err(bad_request("request line too long"));
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("missing request line"));
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+    let mut length = 0;
+    let mut headers = 0;
+    loop {
+        line.clear();
+        match read_line(reader, &mut line)? {
+            Some(0) => return Err(bad_request("unexpected end of the headers")),
+            Some(_) => {}
+            None => return Err(too_large("header too long")),
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Err(too_large("too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request("not correct content length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(bad_request("body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8(body).map_err(|_| bad_request("body is not UTF-8"))?;
+    Ok(Request::new(&method, &target, &body))
+}
+
+/// Server-sent event: the event `name` and its JSON `data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: &'static str,
+    pub data: Json,
+}
+
+impl Event {
+    /// Event in the `text/event-stream` format.
+    pub fn encode(&self) -> String {
+        format!("event: {:}\ndata: {:}\n\n", self.name, self.data)
+    }
+}
+
+// Transaction `node` with its metrics.
+fn transaction(node: &Transaction) -> Json {
+    let parents = match node.parents {
+        Some((left, right)) => Json::object([
+            ("left", Json::Unsigned(left as u64)),
+            ("right", Json::Unsigned(right as u64)),
+        ]),
+        None => Json::Null,
+    };
+    Json::object([
+        ("id", Json::Unsigned(node.id as u64)),
+        ("parents", parents),
+        ("timestamp", Json::Unsigned(node.timestamp as u64)),
+        (
+            "metrics",
+            Json::object([
+                ("depth", Json::Unsigned(node.metrics.depth as u64)),
+                (
+                    "in_reference",
+                    Json::Unsigned(node.metrics.in_reference as u64),
+                ),
+                ("generation", Json::Unsigned(node.metrics.generation as u64)),
+                (
+                    "first_approval",
+                    node.metrics
+                        .first_approval
+                        .map_or(Json::Null, |first| Json::Unsigned(first as u64)),
+                ),
+            ]),
+        ),
+    ])
+}
+
+// Unsigned fields of the flat JSON object `body`, e.g. `{"left": 1}`.
+fn fields(body: &str) -> Option<Vec<(String, u64)>> {
+    let inner = body.trim().strip_prefix('{')?.strip_suffix('}')?;
+    inner
+        .split(',')
+        .filter(|field| !field.trim().is_empty())
+        .map(|field| {
+            let (name, value) = field.split_once(':')?;
+            let name = name.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((name.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn ids(ids: &[u32]) -> Json {
+    Json::Array(ids.iter().map(|id| Json::Unsigned(*id as u64)).collect())
+}
+
+fn sorted(ids: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut ids = ids.into_iter().collect::<Vec<u32>>();
+    ids.sort();
+    ids
+}
+
+/// Ledger served by the API, loaded once and shared by the connections,
+/// the `subscribers` of the events stream and the last statistics
+/// `snapshot` with the number of transactions it was computed for (the
+/// ledger only grows, so it is reused until a transaction is accepted).
+pub struct Server {
+    graph: RwLock<Graph>,
+    subscribers: Mutex<Vec<SyncSender<Event>>>,
+    snapshot: Mutex<Option<(usize, Json)>>,
+}
+
+impl Server {
+    /// Server over graph `graph`.
+    pub fn new(graph: Graph) -> Self {
+        Server {
+            graph: RwLock::new(graph),
+            subscribers: Mutex::new(Vec::new()),
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    /// Receiver of the events published from now on, up to
+    /// `EVENTS_BUFFER` of them pending.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::sync_channel(EVENTS_BUFFER);
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Send `event` to every subscriber without blocking, the closed ones
+    /// and the ones with a full buffer are dropped.
+    pub fn publish(&self, event: Event) {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|subscriber| subscriber.try_send(event.clone()).is_ok());
+    }
+
+    /// Publish a statistics snapshot if there is any subscriber.
+    pub fn publish_statistics(&self) {
+        let subscribed = !self
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty();
+        if subscribed {
+            let data = self.snapshot();
+            self.publish(Event {
+                name: "stats",
+                data,
+            });
+        }
+    }
+
+    /// Route `request` to its endpoint.
+    pub fn handle(&self, request: &Request) -> Response {
+        let segments = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .collect::<Vec<&str>>();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["stats"]) => self.statistics(),
+            ("GET", ["tips"]) => self.tips(),
+            ("GET", ["path"]) => self.path(request),
+            ("GET", ["transactions", id]) => self.transaction(id, None),
+            ("GET", ["transactions", id, cone]) => self.transaction(id, Some(cone)),
+            ("POST", ["transactions"]) => self.submit(&request.body),
+            (_, ["stats"] | ["tips"] | ["path"] | ["transactions", ..]) => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "unknown endpoint"),
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Graph> {
+        self.graph.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Statistics of the current ledger, computed once per number of
+    // transactions.
+    fn snapshot(&self) -> Json {
+        let graph = self.read();
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        match snapshot.as_ref() {
+            Some((nodes, data)) if *nodes == graph.nodes.len() => data.clone(),
+            _ => {
+                let data = json::statistics(&statistics::stats(&graph));
+                *snapshot = Some((graph.nodes.len(), data.clone()));
+                data
+            }
+        }
+    }
+
+    fn statistics(&self) -> Response {
+        Response::ok(self.snapshot())
+    }
+
+    fn tips(&self) -> Response {
+        Response::ok(Json::object([("tips", ids(&self.read().tips()))]))
+    }
+
+    fn transaction(&self, id: &str, cone: Option<&str>) -> Response {
+        let Ok(id) = id.parse() else {
+            return Response::error(400, "not correct transaction id");
+        };
+        let graph = self.read();
+        if !graph.nodes.contains_key(&id) {
+            return Response::error(404, &format!("unknown transaction id=`{:}`", id));
+        }
+        let cone = match cone {
+            None => match inspection::inspect(&graph, id) {
+                Ok(inspection) => return Response::ok(json::inspection(&inspection)),
+                Err(e) => return Response::error(404, &e.to_string()),
+            },
+            Some("past-cone") => graph.past_cone(id),
+            Some("future-cone") => graph.future_cone(id),
+            Some(_) => return Response::error(404, "unknown endpoint"),
+        };
+        Response::ok(Json::object([
+            ("id", Json::Unsigned(id as u64)),
+            ("transactions", ids(&sorted(cone))),
+        ]))
+    }
+
+    fn path(&self, request: &Request) -> Response {
+        let id = |name| request.parameter(name).and_then(|value| value.parse().ok());
+        let (Some(from), Some(to)) = (id("from"), id("to")) else {
+            return Response::error(400, "`from` and `to` transaction ids are needed");
+        };
+        match inspection::paths(&self.read(), from, to, 0) {
+            Ok(paths) => match paths.shortest {
+                Some(path) => Response::ok(Json::object([
+                    ("from", Json::Unsigned(from as u64)),
+                    ("to", Json::Unsigned(to as u64)),
+                    ("path", ids(&path)),
+                    ("count", Json::Unsigned(paths.count)),
+                ])),
+                None => Response::error(404, "no path between the transactions"),
+            },
+            Err(error) => Response::error(404, &error.to_string()),
+        }
+    }
+
+    fn submit(&self, body: &str) -> Response {
+        let Some(fields) = fields(body) else {
+            return Response::error(400, "the body must be a JSON object");
+        };
+        let field = |name| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .and_then(|(_, value)| u32::try_from(*value).ok())
+        };
+        let (Some(left), Some(right), Some(timestamp)) =
+            (field("left"), field("right"), field("timestamp"))
+        else {
+            return Response::error(400, "`left`, `right` and `timestamp` are needed");
+        };
+        // the events are published under the write lock so they follow the
+        // order of the ids
+        let mut graph = self.graph.write().unwrap_or_else(|e| e.into_inner());
+        match graph.append(left, right, timestamp) {
+            Ok(id) => {
+                let data = transaction(&graph.nodes[&id]);
+                let body = json::inspection(
+                    &inspection::inspect(&graph, id).expect("appended transaction exists"),
+                );
+                self.publish(Event {
+                    name: "transaction",
+                    data,
+                });
+                Response { status: 201, body }
+            }
+            Err(e) => {
+                self.publish(Event {
+                    name: "rejected",
+                    data: Json::object([
+                        ("left", Json::Unsigned(left as u64)),
+                        ("right", Json::Unsigned(right as u64)),
+                        ("timestamp", Json::Unsigned(timestamp as u64)),
+                        ("error", Json::String(e.to_string())),
+                    ]),
+                });
+                Response::error(422, &e.to_string())
+            }
+        }
+    }
+
+    // Stream the published events in `stream` until the client leaves.
+    fn events(&self, mut stream: TcpStream) -> io::Result<()> {
+        let events = self.subscribe();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n: connected\n\n"
+        )?;
+        stream.flush()?;
+        loop {
+            match events.recv_timeout(KEEP_ALIVE) {
+                Ok(event) => write!(stream, "{:}", event.encode())?,
+                Err(RecvTimeoutError::Timeout) => write!(stream, ": keep-alive\n\n")?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            stream.flush()?;
+        }
+    }
+
+    fn connection(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader) {
+            Ok(request) if request.method == "GET" && request.path == "/events" => {
+                return self.events(stream)
+            }
+            Ok(request) => self.handle(&request),
+            Err(ServerError::BadRequest(message)) => Response::error(400, &message),
+            Err(ServerError::HeadersTooLarge(message)) => Response::error(431, &message),
+            Err(ServerError::Io(e)) => return Err(e),
+        };
+        response.write_to(&mut &stream)
+    }
+}
+
+// Connection slot taken from `active`, given back when dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    // Slot of `active` if fewer than `max` are taken.
+    fn acquire(active: &Arc<AtomicUsize>, max: usize) -> Option<Slot> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| {
+                (taken < max).then_some(taken + 1)
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Serve `server` on `listener` until the process ends, publishing a
+/// statistics snapshot every `interval`. A failed `accept` is logged and
+/// the server keeps listening.
+pub fn serve(listener: TcpListener, server: Arc<Server>, interval: Duration) -> io::Result<()> {
+    let ticker = Arc::clone(&server);
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        ticker.publish_statistics();
+    });
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("The connection could not be accepted: {:}", e);
+                thread::sleep(ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        let Some(slot) = Slot::acquire(&active, MAX_CONNECTIONS) else {
+            // a short response to a new connection fits in its send buffer
+            let _ = stream.set_write_timeout(Some(TIMEOUT));
+            let _ = Response::error(503, "too many connections").write_to(&mut stream);
+            continue;
+        };
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = server.connection(stream) {
+                eprintln!("The request could not be answered: {:}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    fn server() -> Server {
+        Server::new(Graph::try_from(TEST.to_vec()).unwrap())
+    }
+
+    fn get(server: &Server, target: &str) -> (u16, String) {
+        let response = server.handle(&Request::new("GET", target, ""));
+        (response.status, response.body.to_string())
+    }
+
+    #[test]
+    fn should_limit_the_connection_slots() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let first = Slot::acquire(&active, 2).unwrap();
+        let second = Slot::acquire(&active, 2).unwrap();
+        assert!(Slot::acquire(&active, 2).is_none());
+        drop(first);
+        assert!(Slot::acquire(&active, 2).is_some());
+        drop(second);
+        assert_eq!(0, active.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn should_read_http_requests() {
+        let mut input = Cursor::new(
+            "POST /transactions?x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 7\r\n\r\n{\"a\":1}",
+        );
+        assert_eq!(
+            Request {
+                method: "POST".to_string(),
+                path: "/transactions".to_string(),
+                query: vec![("x".to_string(), "1".to_string())],
+                body: "{\"a\":1}".to_string(),
+            },
+            read_request(&mut input).unwrap()
+        );
+        assert!(matches!(
+            read_request(&mut Cursor::new("\r\n")),
+            Err(ServerError::BadRequest(_))
+        ));
+        assert_eq!(
+            vec![
+                ("from".to_string(), "6".to_string()),
+                ("note".to_string(), "a b&c %zz".to_string()),
+            ],
+            Request::new("GET", "/path?from=%36&note=a+b%26c%20%zz", "").query
+        );
+    }
+
+    #[test]
+    fn should_reject_oversized_requests() {
+        let long = format!("GET /{:} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(matches!(
+            read_request(&mut Cursor::new(long)),
+            Err(ServerError::BadRequest(_))
+        ));
+        let header = format!("GET / HTTP/1.1\r\nX: {:}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(matches!(
+            read_request(&mut Cursor::new(header)),
+            Err(ServerError::HeadersTooLarge(_))
+        ));
+        let headers = format!(
+            "GET / HTTP/1.1\r\n{:}\r\n",
+            "X: a\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(matches!(
+            read_request(&mut Cursor::new(headers)),
+            Err(ServerError::HeadersTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn should_answer_queries() {
+        let server = server();
+        assert_eq!(
+            (200, r#"{"tips":[5,6]}"#.to_string()),
+            get(&server, "/tips")
+        );
+        assert_eq!(
+            (200, r#"{"id":3,"transactions":[5,6]}"#.to_string()),
+            get(&server, "/transactions/3/future-cone")
+        );
+        assert_eq!(
+            (
+                200,
+                r#"{"from":6,"to":1,"path":[6,3,1],"count":3}"#.to_string()
+            ),
+            get(&server, "/path?from=6&to=1")
+        );
+        assert_eq!(
+            (
+                200,
+                r#"{"from":2,"to":6,"path":[6,3,2],"count":2}"#.to_string()
+            ),
+            get(&server, "/path?from=2&to=6")
+        );
+        assert!(get(&server, "/stats")
+            .1
+            .contains(r#""average_nodes_by_depth":2.5"#));
+        assert!(get(&server, "/transactions/3")
+            .1
+            .contains(r#""approvers":[5,6]"#));
+        assert_eq!(404, get(&server, "/transactions/9").0);
+        assert_eq!(400, get(&server, "/path?from=6").0);
+        assert_eq!(404, get(&server, "/unknown").0);
+    }
+
+    #[test]
+    fn should_validate_submitted_transactions() {
+        let server = server();
+        let post = |body| server.handle(&Request::new("POST", "/transactions", body));
+        assert!(get(&server, "/stats").1.contains(r#""last_transaction":6"#));
+        let created = post(r#"{"left": 5, "right": 6, "timestamp": 4}"#);
+        assert_eq!(201, created.status);
+        // the statistics snapshot follows the accepted transaction
+        assert!(get(&server, "/stats").1.contains(r#""last_transaction":7"#));
+        assert!(created
+            .body
+            .to_string()
+            .contains(r#""id":7,"parents":{"left":5,"right":6}"#));
+        assert_eq!(
+            r#"{"tips":[7]}"#,
+            server
+                .handle(&Request::new("GET", "/tips", ""))
+                .body
+                .to_string()
+        );
+        let rejected = post(r#"{"left": 9, "right": 6, "timestamp": 4}"#);
+        assert_eq!(
+            (422, r#"{"error":"unknown parent"}"#.to_string()),
+            (rejected.status, rejected.body.to_string())
+        );
+        assert_eq!(400, post("1 2 3").status);
+        assert_eq!(404, get(&server, "/events").0);
+        assert_eq!(
+            405,
+            server.handle(&Request::new("DELETE", "/tips", "")).status
+        );
+    }
+
+    #[test]
+    fn should_publish_accepted_and_rejected_transactions() {
+        let server = server();
+        let events = server.subscribe();
+        let post = |body| server.handle(&Request::new("POST", "/transactions", body));
+        post(r#"{"left": 5, "right": 5, "timestamp": 4}"#);
+        post(r#"{"left": 9, "right": 5, "timestamp": 4}"#);
+        server.publish_statistics();
+        assert_eq!(
+            "event: transaction\ndata: {\"id\":7,\"parents\":{\"left\":5,\"right\":5},\"timestamp\":4,\"metrics\":{\"depth\":3,\"in_reference\":0,\"generation\":4,\"first_approval\":null}}\n\n",
+            events.recv().unwrap().encode()
+        );
+        assert_eq!(
+            Event {
+                name: "rejected",
+                data: Json::object([
+                    ("left", Json::Unsigned(9)),
+                    ("right", Json::Unsigned(5)),
+                    ("timestamp", Json::Unsigned(4)),
+                    ("error", Json::String("unknown parent".to_string())),
+                ]),
+            },
+            events.recv().unwrap()
+        );
+        assert_eq!("stats", events.recv().unwrap().name);
+        drop(events);
+        server.publish_statistics();
+        assert!(server.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_drop_lagging_subscribers() {
+        let server = server();
+        let events = server.subscribe();
+        (0..=EVENTS_BUFFER).for_each(|_| server.publish_statistics());
+        assert!(server.subscribers.lock().unwrap().is_empty());
+        assert_eq!(EVENTS_BUFFER, events.iter().count());
+    }
+}
@@ -4,7 +4,7 @@
 /// Data transfer objects
 pub mod dto {
     /// Statistics structure for displaying metrics data.
-    #[derive(Debug)]
+    #[derive(Debug, PartialEq)]
     pub struct Statistics {
         pub average_depth: f64,
         pub average_nodes_by_depth: f64,
@@ -12,83 +12,1404 @@ pub mod dto {
         pub last_transaction: u32,
         pub most_referenced_transaction: u32,
         pub range_timestamps: Vec<(u32, u64)>,
+        pub same_parent_transactions: u64,
+        pub distinct_parent_transactions: u64,
+        pub max_layer_width: u64,
+        pub max_width: u64,
+        pub transactions_by_depth: Vec<(u32, u64)>,
+        pub width_over_time: Vec<(u32, u64)>,
+    }
+
+    /// Typed result of a single statistic.
+    ///
+    /// - `ByDepth` counts keyed by depth level.
+    /// - `ByTimeRange` counts keyed by timestamp range (`timestamp / TIMESTAMP_RANGE`).
+    /// - `Transactions` identifiers sorted by id.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Value {
+        Float(f64),
+        Count(u64),
+        Transaction(u32),
+        ByDepth(Vec<(u32, u64)>),
+        ByTimeRange(Vec<(u32, u64)>),
+        Transactions(Vec<u32>),
+    }
+
+    /// Details of a single transaction.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Inspection {
+        pub id: u32,
+        pub parents: Option<(u32, u32)>,
+        pub timestamp: u32,
+        pub depth: u32,
+        pub generation: u32,
+        pub in_reference: u32,
+        pub approvers: Vec<u32>,
+        pub cumulative_weight: u64,
+        pub past_cone: u64,
+        pub future_cone: u64,
+        pub tip: bool,
+    }
+
+    /// Paths of references between two transactions: the `shortest` one
+    /// from the newer to the older, the `count` of distinct paths and the
+    /// listed `paths`.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Paths {
+        pub from: u32,
+        pub to: u32,
+        pub shortest: Option<Vec<u32>>,
+        pub count: u64,
+        pub paths: Vec<Vec<u32>>,
+    }
+
+    /// Common ancestor of two transactions with its `depth` and `timestamp`.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Ancestor {
+        pub id: u32,
+        pub depth: u32,
+        pub timestamp: u32,
+    }
+
+    /// Confirmation of a transaction: its `cumulative_weight`, the
+    /// percentage of current tips approving it (`coverage`) and whether
+    /// it is `confirmed` under the rule.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Confirmation {
+        pub id: u32,
+        pub cumulative_weight: u64,
+        pub coverage: f64,
+        pub confirmed: bool,
+    }
+
+    /// Confirmations under `rule`: the `confirmed` transactions of `total`,
+    /// their `rate`, `(range, confirmed, total)` for every timestamp range
+    /// and the confirmation of every transaction sorted by id.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Confirmations {
+        pub rule: String,
+        pub confirmed: u64,
+        pub total: u64,
+        pub rate: f64,
+        pub by_time_range: Vec<(u32, u64, u64)>,
+        pub transactions: Vec<Confirmation>,
+    }
+
+    /// Milestone `id` issued at `timestamp`, `interval` time units after the
+    /// previous milestone, that first `confirmed` a number of transactions.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Milestone {
+        pub id: u32,
+        pub timestamp: u32,
+        pub interval: Option<u32>,
+        pub confirmed: u64,
+    }
+
+    /// Confirmation of transaction `id` by the first `milestone` whose past
+    /// cone contains it, `latency` time units after its timestamp.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MilestoneConfirmation {
+        pub id: u32,
+        pub milestone: Option<u32>,
+        pub latency: Option<u32>,
+    }
+
+    /// Milestone confirmations: the `milestones` sorted by id, the
+    /// `confirmed` and `unconfirmed` transactions, the average interval
+    /// between milestones, the average and maximum confirmation latencies,
+    /// the latencies by time range and the confirmation of every
+    /// transaction sorted by id.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Milestones {
+        pub milestones: Vec<Milestone>,
+        pub confirmed: u64,
+        pub unconfirmed: u64,
+        pub average_interval: f64,
+        pub average_latency: f64,
+        pub max_latency: u32,
+        pub latencies: Vec<(u32, u64)>,
+        pub transactions: Vec<MilestoneConfirmation>,
+    }
+
+    /// Resolution of a conflict set: its `transactions` with their
+    /// cumulative weights (without the transactions invalidated by the sets
+    /// resolved before) sorted by id, the `winner` (none when all of them
+    /// were already invalidated) and the transactions `invalidated` with
+    /// the losers (the losers and their future cones).
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Conflict {
+        pub transactions: Vec<(u32, u64)>,
+        pub winner: Option<u32>,
+        pub invalidated: u64,
+    }
+
+    /// Conflict analysis: the resolution of every conflict set, the
+    /// `inconsistent` transactions (with two conflicting transactions in
+    /// their past cones), the `subtangles` rooted at a loser and every
+    /// transaction `invalidated` of `total`. Identifiers are sorted.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Conflicts {
+        pub conflicts: Vec<Conflict>,
+        pub inconsistent: Vec<u32>,
+        pub subtangles: u64,
+        pub invalidated: Vec<u32>,
+        pub total: u64,
+    }
+
+    /// Result of a statistic computed by the registry.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Entry {
+        pub name: String,
+        pub label: String,
+        pub value: Value,
     }
 }
 
 /// Statistics services
 pub mod statistics {
     use super::dto;
+    use crate::domain::Transaction;
     use crate::graph::Graph;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
+    use thiserror::Error;
     pub const TIMESTAMP_RANGE: u32 = 10;
 
-    fn average_depth(graph: &Graph) -> f64 {
-        graph
+    /// Fields of the statistics documents (the `dto::Statistics` fields and
+    /// the JSON header), the registered statistics cannot take their names.
+    pub const RESERVED_NAMES: [&str; 14] = [
+        "schema_version",
+        "timestamp_range",
+        "average_depth",
+        "average_nodes_by_depth",
+        "average_in_references",
+        "last_transaction",
+        "most_referenced_transaction",
+        "same_parent_transactions",
+        "distinct_parent_transactions",
+        "max_layer_width",
+        "max_width",
+        "range_timestamps",
+        "transactions_by_depth",
+        "width_over_time",
+    ];
+
+    /// Set of possible errors selecting statistics.
+    #[derive(Error, Debug, PartialEq)]
+    pub enum StatisticsError {
+        #[error("unknown statistic `{0}`")]
+        UnknownStatistic(String),
+        #[error("statistic `{0}` already registered")]
+        DuplicatedStatistic(String),
+        #[error("statistic name `{0}` is a field of the statistics output")]
+        ReservedStatistic(String),
+        #[error("statistic name `{0}` does not match [a-z_][a-z0-9_]*")]
+        InvalidStatisticName(String),
+    }
+
+    /// A metric over the graph. Custom metrics implement this trait and
+    /// are added to a `Registry` next to the built-in ones.
+    pub trait Statistic {
+        /// Unique name used for selecting the statistic.
+        fn name(&self) -> &str;
+        /// Label used for displaying the result.
+        fn label(&self) -> &str;
+        /// Calculate the statistic from graph `graph`.
+        fn compute(&self, graph: &Graph) -> dto::Value;
+        /// Calculate the statistic reusing the `summary` of the single pass
+        /// engine. By default it ignores it and calls `compute`.
+        fn compute_with(&self, graph: &Graph, _summary: &dto::Statistics) -> dto::Value {
+            self.compute(graph)
+        }
+        /// Accumulators of the single pass engine read by `compute_with`,
+        /// none by default.
+        fn accumulated(&self) -> Accumulated {
+            Accumulated::default()
+        }
+        /// Only computed when selected by name, not with the default
+        /// statistics (false by default).
+        fn opt_in(&self) -> bool {
+            false
+        }
+    }
+
+    /// Partial states of the single pass engine. Only the enabled ones are
+    /// fed with the nodes, the fields of `dto::Statistics` derived from the
+    /// disabled ones are left empty.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Accumulated {
+        /// Depth sum and nodes per depth.
+        pub depths: bool,
+        /// In reference sum.
+        pub in_references: bool,
+        /// Transactions with the same and with distinct parents.
+        pub parents: bool,
+        /// Nodes per generation.
+        pub generations: bool,
+        /// Nodes per timestamp range.
+        pub timestamps: bool,
+        /// Open tips over time.
+        pub open_tips: bool,
+        /// Visited transactions, for the widest antichain.
+        pub antichains: bool,
+    }
+
+    impl Accumulated {
+        /// Every accumulator enabled.
+        pub const ALL: Accumulated = Accumulated {
+            depths: true,
+            in_references: true,
+            parents: true,
+            generations: true,
+            timestamps: true,
+            open_tips: true,
+            antichains: true,
+        };
+
+        /// Accumulators enabled in `self` or in `other`.
+        pub fn union(self, other: Accumulated) -> Accumulated {
+            Accumulated {
+                depths: self.depths || other.depths,
+                in_references: self.in_references || other.in_references,
+                parents: self.parents || other.parents,
+                generations: self.generations || other.generations,
+                timestamps: self.timestamps || other.timestamps,
+                open_tips: self.open_tips || other.open_tips,
+                antichains: self.antichains || other.antichains,
+            }
+        }
+    }
+
+    /// Built-in statistic, it picks its value from the single pass summary.
+    struct BuiltIn {
+        name: &'static str,
+        label: &'static str,
+        accumulated: Accumulated,
+        extract: fn(&dto::Statistics) -> dto::Value,
+    }
+
+    impl Statistic for BuiltIn {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn label(&self) -> &str {
+            self.label
+        }
+        fn compute(&self, graph: &Graph) -> dto::Value {
+            (self.extract)(&stats_with(graph, self.accumulated, 1))
+        }
+        fn compute_with(&self, _graph: &Graph, summary: &dto::Statistics) -> dto::Value {
+            (self.extract)(summary)
+        }
+        fn accumulated(&self) -> Accumulated {
+            self.accumulated
+        }
+    }
+
+    /// Set of available statistics, in display order. The first `builtins`
+    /// ones are the built-in statistics, computed with `threads` workers
+    /// (sequentially by default).
+    #[derive(Default)]
+    pub struct Registry {
+        statistics: Vec<Box<dyn Statistic>>,
+        builtins: usize,
+        threads: usize,
+    }
+
+    impl Registry {
+        /// Constructor with all the built-in statistics registered.
+        pub fn with_builtins() -> Self {
+            let builtins: [BuiltIn; 12] = [
+                BuiltIn {
+                    name: "avg_depth",
+                    label: "AVG DAG DEPTH",
+                    accumulated: Accumulated {
+                        depths: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Float(stats.average_depth),
+                },
+                BuiltIn {
+                    name: "avg_txs_per_depth",
+                    label: "AVG TXS PER DEPTH",
+                    accumulated: Accumulated {
+                        depths: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Float(stats.average_nodes_by_depth),
+                },
+                BuiltIn {
+                    name: "avg_ref",
+                    label: "AVG REF",
+                    accumulated: Accumulated {
+                        in_references: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Float(stats.average_in_references),
+                },
+                BuiltIn {
+                    name: "last_transaction",
+                    label: "TRANS LAST",
+                    accumulated: Accumulated::default(),
+                    extract: |stats| dto::Value::Transaction(stats.last_transaction),
+                },
+                BuiltIn {
+                    name: "most_referenced",
+                    label: "TRANS MOST IN REF",
+                    accumulated: Accumulated::default(),
+                    extract: |stats| dto::Value::Transaction(stats.most_referenced_transaction),
+                },
+                BuiltIn {
+                    name: "same_parents",
+                    label: "TRANS SAME PARENTS",
+                    accumulated: Accumulated {
+                        parents: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Count(stats.same_parent_transactions),
+                },
+                BuiltIn {
+                    name: "distinct_parents",
+                    label: "TRANS DISTINCT PARENTS",
+                    accumulated: Accumulated {
+                        parents: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Count(stats.distinct_parent_transactions),
+                },
+                BuiltIn {
+                    name: "max_layer_width",
+                    label: "MAX LAYER WIDTH",
+                    accumulated: Accumulated {
+                        generations: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Count(stats.max_layer_width),
+                },
+                BuiltIn {
+                    name: "max_width",
+                    label: "MAX WIDTH",
+                    accumulated: Accumulated {
+                        antichains: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::Count(stats.max_width),
+                },
+                BuiltIn {
+                    name: "transactions_by_depth",
+                    label: "TXS BY DEPTH",
+                    accumulated: Accumulated {
+                        depths: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::ByDepth(stats.transactions_by_depth.clone()),
+                },
+                BuiltIn {
+                    name: "timestamps",
+                    label: "TIMESTAMPS",
+                    accumulated: Accumulated {
+                        timestamps: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::ByTimeRange(stats.range_timestamps.clone()),
+                },
+                BuiltIn {
+                    name: "width_over_time",
+                    label: "WIDTH OVER TIME",
+                    accumulated: Accumulated {
+                        open_tips: true,
+                        ..Accumulated::default()
+                    },
+                    extract: |stats| dto::Value::ByTimeRange(stats.width_over_time.clone()),
+                },
+            ];
+            let mut registry = Registry::default();
+            for builtin in builtins {
+                registry
+                    .insert(Box::new(builtin))
+                    .expect("built-in statistic names are unique");
+            }
+            registry.builtins = registry.statistics.len();
+            registry
+        }
+
+        /// Add a new statistic `statistic` at the end of the registry. It
+        /// fails when another statistic has the same name, when the name is
+        /// one of the `RESERVED_NAMES` or when it does not match
+        /// `[a-z_][a-z0-9_]*` (valid as JSON key and Prometheus metric name).
+        pub fn register(&mut self, statistic: Box<dyn Statistic>) -> Result<(), StatisticsError> {
+            let name = statistic.name();
+            let mut chars = name.chars();
+            let valid = chars
+                .next()
+                .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+                && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(StatisticsError::InvalidStatisticName(name.to_string()));
+            }
+            if RESERVED_NAMES.contains(&name) {
+                return Err(StatisticsError::ReservedStatistic(name.to_string()));
+            }
+            self.insert(statistic)
+        }
+
+        // Add `statistic` at the end of the registry unless another one has
+        // the same name.
+        fn insert(&mut self, statistic: Box<dyn Statistic>) -> Result<(), StatisticsError> {
+            if self.statistics.iter().any(|s| s.name() == statistic.name()) {
+                return Err(StatisticsError::DuplicatedStatistic(
+                    statistic.name().to_string(),
+                ));
+            }
+            self.statistics.push(statistic);
+            Ok(())
+        }
+
+        /// Number of workers `threads` used for the built-in statistics.
+        pub fn set_threads(&mut self, threads: usize) {
+            self.threads = threads;
+        }
+
+        /// Names of the registered statistics.
+        pub fn names(&self) -> Vec<&str> {
+            self.statistics.iter().map(|s| s.name()).collect()
+        }
+
+        /// Compute the statistics registered after the built-in ones from
+        /// graph `graph`, in registration order, without the opt-in ones.
+        pub fn compute_custom(&self, graph: &Graph) -> Vec<dto::Entry> {
+            self.statistics[self.builtins..]
+                .iter()
+                .filter(|statistic| !statistic.opt_in())
+                .map(|statistic| dto::Entry {
+                    name: statistic.name().to_string(),
+                    label: statistic.label().to_string(),
+                    value: statistic.compute(graph),
+                })
+                .collect()
+        }
+
+        /// Compute the statistics in `selection` (in the given order) from graph
+        /// `graph`. An empty selection computes all the registered statistics
+        /// but the opt-in ones.
+        /// The built-in statistics share a single traversal of the nodes that
+        /// only feeds the accumulators they read.
+        pub fn compute(
+            &self,
+            graph: &Graph,
+            selection: &[String],
+        ) -> Result<Vec<dto::Entry>, StatisticsError> {
+            let selected = if selection.is_empty() {
+                self.statistics
+                    .iter()
+                    .filter(|statistic| !statistic.opt_in())
+                    .collect::<Vec<&Box<dyn Statistic>>>()
+            } else {
+                selection
+                    .iter()
+                    .map(|name| {
+                        self.statistics
+                            .iter()
+                            .find(|s| s.name() == name)
+                            .ok_or_else(|| StatisticsError::UnknownStatistic(name.clone()))
+                    })
+                    .collect::<Result<Vec<&Box<dyn Statistic>>, StatisticsError>>()?
+            };
+            let accumulated = selected
+                .iter()
+                .fold(Accumulated::default(), |accumulated, statistic| {
+                    accumulated.union(statistic.accumulated())
+                });
+            let summary = stats_with(graph, accumulated, self.threads);
+            Ok(selected
+                .iter()
+                .map(|statistic| dto::Entry {
+                    name: statistic.name().to_string(),
+                    label: statistic.label().to_string(),
+                    value: statistic.compute_with(graph, &summary),
+                })
+                .collect())
+        }
+    }
+
+    // Sorted `(key, count)` pairs from a histogram.
+    fn sorted(histogram: HashMap<u32, u64>) -> Vec<(u32, u64)> {
+        let mut items = histogram.into_iter().collect::<Vec<(u32, u64)>>();
+        items.sort_by_key(|&k| k);
+        items
+    }
+
+    // Depths and generations are dense (a node in level `n` always has a
+    // parent in level `n - 1`), so their histograms are indexed vectors.
+    fn increment(histogram: &mut Vec<u64>, level: u32) {
+        let level = level as usize;
+        if histogram.len() <= level {
+            histogram.resize(level + 1, 0);
+        }
+        histogram[level] += 1;
+    }
+
+    fn merge_levels(histogram: &mut Vec<u64>, other: Vec<u64>) {
+        if histogram.len() < other.len() {
+            histogram.resize(other.len(), 0);
+        }
+        for (level, count) in other.into_iter().enumerate() {
+            histogram[level] += count;
+        }
+    }
+
+    /// Partial state of every built-in statistic. The engine feeds each node
+    /// once to `visit` and derives all the statistics in `finish`, instead of
+    /// iterating the node store once per statistic.
+    #[derive(Debug, Default)]
+    struct Accumulators {
+        enabled: Accumulated,
+        num_nodes: u64,
+        depth_sum: u64,
+        in_reference_sum: u64,
+        same_parents: u64,
+        with_parents: u64,
+        by_depth: Vec<u64>,
+        by_generation: Vec<u64>,
+        by_range: HashMap<u32, u64>,
+        open_tips: BTreeMap<u32, i64>,
+        members: Vec<u32>,
+    }
+
+    impl Accumulators {
+        fn new(enabled: Accumulated) -> Self {
+            Accumulators {
+                enabled,
+                ..Accumulators::default()
+            }
+        }
+
+        // Merge the partial state `other` computed over another partition
+        // of the nodes. Every field is an integer sum or histogram, so the
+        // result does not depend on how the nodes were partitioned.
+        fn merge(&mut self, other: Accumulators) {
+            self.num_nodes += other.num_nodes;
+            self.depth_sum += other.depth_sum;
+            self.in_reference_sum += other.in_reference_sum;
+            self.same_parents += other.same_parents;
+            self.with_parents += other.with_parents;
+            merge_levels(&mut self.by_depth, other.by_depth);
+            merge_levels(&mut self.by_generation, other.by_generation);
+            for (range, count) in other.by_range {
+                *self.by_range.entry(range).or_insert(0) += count;
+            }
+            for (range, change) in other.open_tips {
+                *self.open_tips.entry(range).or_insert(0) += change;
+            }
+            self.members.extend(other.members);
+        }
+
+        fn visit(&mut self, node: &Transaction) {
+            let enabled = self.enabled;
+            self.num_nodes += 1;
+            if enabled.depths {
+                self.depth_sum += node.metrics.depth as u64;
+                increment(&mut self.by_depth, node.metrics.depth);
+            }
+            if enabled.in_references {
+                self.in_reference_sum += node.metrics.in_reference as u64;
+            }
+            if enabled.parents {
+                if node.parents.is_some() {
+                    self.with_parents += 1;
+                }
+                if node.has_same_parents() {
+                    self.same_parents += 1;
+                }
+            }
+            if enabled.generations {
+                increment(&mut self.by_generation, node.metrics.generation);
+            }
+            if enabled.timestamps {
+                *self
+                    .by_range
+                    .entry(node.timestamp / TIMESTAMP_RANGE)
+                    .or_insert(0) += 1;
+            }
+            if enabled.open_tips {
+                self.visit_open_tip(node);
+            }
+            if enabled.antichains {
+                self.members.push(node.id);
+            }
+        }
+
+        // A transaction is an open tip from its timestamp range until the
+        // range of its first approval. An approval with an older timestamp
+        // than the approved transaction closes it in its own range.
+        fn visit_open_tip(&mut self, node: &Transaction) {
+            let opened = node.timestamp / TIMESTAMP_RANGE;
+            let closed = node
+                .metrics
+                .first_approval
+                .map(|first| first.max(node.timestamp) / TIMESTAMP_RANGE);
+            if closed == Some(opened) {
+                return;
+            }
+            *self.open_tips.entry(opened).or_insert(0) += 1;
+            if let Some(closed) = closed {
+                *self.open_tips.entry(closed).or_insert(0) -= 1;
+            }
+        }
+
+        // Number of open tips at the end of every timestamp range where a
+        // tip is opened or closed. Only those ranges are visited, so sparse
+        // timestamps (e.g. epoch seconds) do not walk the empty ranges.
+        fn width_over_time(&self) -> Vec<(u32, u64)> {
+            let mut open_tips = 0;
+            self.open_tips
+                .iter()
+                .map(|(&range, &change)| {
+                    open_tips += change;
+                    (range, open_tips as u64)
+                })
+                .collect()
+        }
+
+        // Statistics of the visited nodes, the averages are over `num_nodes`.
+        fn finish(self, graph: &Graph, num_nodes: u64) -> dto::Statistics {
+            let num_nodes = num_nodes as f64;
+            // Depth 0 (the root) is not included in the nodes per depth.
+            let levels = self
+                .by_depth
+                .iter()
+                .skip(1)
+                .copied()
+                .filter(|&count| count != 0)
+                .collect::<Vec<u64>>();
+            let width_over_time = self.width_over_time();
+            dto::Statistics {
+                average_depth: self.depth_sum as f64 / num_nodes,
+                average_nodes_by_depth: levels.iter().sum::<u64>() as f64 / levels.len() as f64,
+                average_in_references: self.in_reference_sum as f64 / num_nodes,
+                last_transaction: graph.metrics.last_transaction,
+                most_referenced_transaction: graph.metrics.most_in_reference_transaction,
+                range_timestamps: sorted(self.by_range),
+                same_parent_transactions: self.same_parents,
+                distinct_parent_transactions: self.with_parents - self.same_parents,
+                // Size of the widest layer of nodes by generation (longest
+                // path from root). A node is always in a greater generation
+                // than its ancestors, so a layer is an antichain and its size
+                // a lower bound of the widest one (Dilworth's width).
+                max_layer_width: self.by_generation.into_iter().max().unwrap_or(0),
+                // Widest antichain of the visited nodes, ordered by the
+                // references of the whole graph (Dilworth's width).
+                max_width: graph.width_of(&self.members),
+                transactions_by_depth: self
+                    .by_depth
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(_, count)| count != 0)
+                    .map(|(depth, count)| (depth as u32, count))
+                    .collect(),
+                width_over_time,
+            }
+        }
+    }
+
+    /// Calculate statistics from graph `graph` in a single traversal of
+    /// its nodes.
+    pub fn stats(graph: &Graph) -> dto::Statistics {
+        stats_with(graph, Accumulated::ALL, 1)
+    }
+
+    /// Calculate statistics over the transactions of graph `graph` matching
+    /// `predicate`. The averages are over the matching transactions, the
+    /// last transaction is the matching one with the last timestamp and the
+    /// most referenced one the matching one with most in references, ties
+    /// are resolved by the lowest id (0 without matches).
+    pub fn stats_matching(
+        graph: &Graph,
+        predicate: impl Fn(&Transaction) -> bool,
+    ) -> dto::Statistics {
+        let mut accumulators = Accumulators::new(Accumulated::ALL);
+        let mut last: Option<&Transaction> = None;
+        let mut most_referenced: Option<&Transaction> = None;
+        for node in graph.nodes.values().filter(|node| predicate(node)) {
+            accumulators.visit(node);
+            if last.is_none_or(|last| (node.timestamp, last.id) > (last.timestamp, node.id)) {
+                last = Some(node);
+            }
+            if most_referenced.is_none_or(|most| {
+                (node.metrics.in_reference, most.id) > (most.metrics.in_reference, node.id)
+            }) {
+                most_referenced = Some(node);
+            }
+        }
+        let matching = accumulators.num_nodes;
+        dto::Statistics {
+            last_transaction: last.map_or(0, |node| node.id),
+            most_referenced_transaction: most_referenced.map_or(0, |node| node.id),
+            ..accumulators.finish(graph, matching)
+        }
+    }
+
+    /// Number of transactions for every number of in references (indegree),
+    /// sorted by in references.
+    pub fn in_reference_distribution(graph: &Graph) -> Vec<(u32, u64)> {
+        let mut distribution: HashMap<u32, u64> = HashMap::new();
+        for node in graph.nodes.values() {
+            *distribution.entry(node.metrics.in_reference).or_insert(0) += 1;
+        }
+        sorted(distribution)
+    }
+
+    /// The `limit` transactions with most in references as `(id, in references)`,
+    /// ties are sorted by id.
+    pub fn top_referenced(graph: &Graph, limit: usize) -> Vec<(u32, u32)> {
+        let mut referenced = graph
+            .nodes
+            .values()
+            .map(|node| (node.id, node.metrics.in_reference))
+            .collect::<Vec<(u32, u32)>>();
+        referenced.sort_by_key(|&(id, in_reference)| (std::cmp::Reverse(in_reference), id));
+        referenced.truncate(limit);
+        referenced
+    }
+
+    /// Calculate statistics from graph `graph` splitting its nodes in
+    /// `threads` partitions computed in parallel. The partial results are
+    /// merged, so it returns the same statistics as `stats`.
+    pub fn stats_parallel(graph: &Graph, threads: usize) -> dto::Statistics {
+        stats_with(graph, Accumulated::ALL, threads)
+    }
+
+    /// Calculate the statistics derived from the `accumulated` partial
+    /// states of graph `graph` with `threads` workers. The averages are over
+    /// `graph.num_nodes`. Without accumulators the nodes are not traversed.
+    pub fn stats_with(graph: &Graph, accumulated: Accumulated, threads: usize) -> dto::Statistics {
+        let num_nodes = graph.num_nodes as u64;
+        if accumulated == Accumulated::default() {
+            return Accumulators::new(accumulated).finish(graph, num_nodes);
+        }
+        if threads <= 1 {
+            let mut accumulators = Accumulators::new(accumulated);
+            for node in graph.nodes.values() {
+                accumulators.visit(node);
+            }
+            return accumulators.finish(graph, num_nodes);
+        }
+        let nodes = graph.nodes.values().collect::<Vec<&Transaction>>();
+        let chunk_size = nodes.len().div_ceil(threads).max(1);
+        let partials = std::thread::scope(|scope| {
+            let workers = nodes
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut accumulators = Accumulators::new(accumulated);
+                        for node in chunk {
+                            accumulators.visit(node);
+                        }
+                        accumulators
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("statistics worker panicked"))
+                .collect::<Vec<Accumulators>>()
+        });
+        let mut accumulators = Accumulators::new(accumulated);
+        for partial in partials {
+            accumulators.merge(partial);
+        }
+        accumulators.finish(graph, num_nodes)
+    }
+}
+
+/// Liveness services, they classify the transactions as confirmed, pending
+/// or left behind.
+pub mod liveness {
+    use super::confirmation::{self, Rule};
+    use super::dto;
+    use super::statistics::{Registry, Statistic, StatisticsError, TIMESTAMP_RANGE};
+    use crate::graph::Graph;
+
+    /// Classification thresholds: a transaction is confirmed under the
+    /// `confirmation` rule. Otherwise it is left behind when its last
+    /// approval (its own timestamp without approvals) is more than `max_age`
+    /// before the latest timestamp, and pending when it is not.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Thresholds {
+        pub max_age: u32,
+        pub confirmation: Rule,
+    }
+
+    impl Default for Thresholds {
+        fn default() -> Self {
+            Thresholds {
+                max_age: 5 * TIMESTAMP_RANGE,
+                confirmation: Rule::default(),
+            }
+        }
+    }
+
+    /// Liveness of a transaction.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Status {
+        Confirmed,
+        Pending,
+        LeftBehind,
+    }
+
+    /// Status of every transaction of graph `graph` under `thresholds`,
+    /// sorted by id. The confirmations are the ones of
+    /// `confirmation::confirmations`.
+    pub fn classify(graph: &Graph, thresholds: &Thresholds) -> Vec<(u32, Status)> {
+        let latest = graph
             .nodes
             .values()
-            .map(|node| node.metrics.depth)
-            .sum::<u32>() as f64
-            / graph.num_nodes as f64
-    }
-    fn average_nodes_by_depth(graph: &Graph) -> f64 {
-        let mut score_depth = HashMap::new();
-        for node in graph.nodes.values().filter(|v| v.id != 1) {
-            let entry = score_depth.entry(node.metrics.depth).or_insert(0);
-            *entry += 1;
-        }
-        let num_scores = score_depth.len() as f64;
-        let depths = score_depth.into_values().sum::<u32>() as f64;
-        depths / num_scores
-    }
-    fn average_in_references(graph: &Graph) -> f64 {
-        graph
+            .map(|node| node.timestamp)
+            .max()
+            .unwrap_or(0);
+        confirmation::confirmations(graph, &thresholds.confirmation)
+            .transactions
+            .into_iter()
+            .map(|confirmation| {
+                let node = &graph.nodes[&confirmation.id];
+                let last_activity = graph
+                    .approvers(node.id)
+                    .iter()
+                    .map(|approver| graph.nodes[approver].timestamp)
+                    .max()
+                    .unwrap_or(node.timestamp);
+                let status = if confirmation.confirmed {
+                    Status::Confirmed
+                } else if latest.saturating_sub(last_activity) > thresholds.max_age {
+                    Status::LeftBehind
+                } else {
+                    Status::Pending
+                };
+                (node.id, status)
+            })
+            .collect()
+    }
+
+    /// Transactions of graph `graph` with status `status`, sorted by id.
+    pub fn transactions(graph: &Graph, thresholds: &Thresholds, status: Status) -> Vec<u32> {
+        classify(graph, thresholds)
+            .into_iter()
+            .filter(|&(_, other)| other == status)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Statistic listing the transactions with a status.
+    struct Liveness {
+        status: Status,
+        thresholds: Thresholds,
+    }
+
+    impl Statistic for Liveness {
+        fn name(&self) -> &str {
+            match self.status {
+                Status::Confirmed => "confirmed",
+                Status::Pending => "pending",
+                Status::LeftBehind => "left_behind",
+            }
+        }
+        fn label(&self) -> &str {
+            match self.status {
+                Status::Confirmed => "TRANS CONFIRMED",
+                Status::Pending => "TRANS PENDING",
+                Status::LeftBehind => "TRANS LEFT BEHIND",
+            }
+        }
+        fn compute(&self, graph: &Graph) -> dto::Value {
+            dto::Value::Transactions(transactions(graph, &self.thresholds, self.status))
+        }
+        fn opt_in(&self) -> bool {
+            true
+        }
+    }
+
+    /// Add the `confirmed`, `pending` and `left_behind` statistics with
+    /// `thresholds` to `registry`. They are opt-in, computed only when
+    /// selected by name.
+    pub fn register(
+        registry: &mut Registry,
+        thresholds: Thresholds,
+    ) -> Result<(), StatisticsError> {
+        for status in [Status::Confirmed, Status::Pending, Status::LeftBehind] {
+            let thresholds = thresholds.clone();
+            registry.register(Box::new(Liveness { status, thresholds }))?;
+        }
+        Ok(())
+    }
+}
+
+/// Confirmation services, they label the transactions as confirmed under a
+/// configurable rule.
+pub mod confirmation {
+    use super::dto;
+    use super::statistics::TIMESTAMP_RANGE;
+    use crate::graph::Graph;
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::str::FromStr;
+    use thiserror::Error;
+
+    /// Set of possible errors parsing confirmation rules.
+    #[derive(Error, Debug, PartialEq)]
+    pub enum ConfirmationError {
+        #[error("not correct rule `{0}`, expected e.g. `weight >= 10 or coverage >= 66%`")]
+        InvalidRule(String),
+    }
+
+    /// Confirmation rule: a cumulative weight of at least `Weight`, a
+    /// percentage of the current tips approving the transaction directly or
+    /// indirectly of at least `Coverage`, or a combination of rules.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Rule {
+        Weight(u64),
+        Coverage(f64),
+        And(Box<Rule>, Box<Rule>),
+        Or(Box<Rule>, Box<Rule>),
+    }
+
+    impl Default for Rule {
+        fn default() -> Self {
+            Rule::Weight(10)
+        }
+    }
+
+    impl Rule {
+        /// It checks if a transaction with `cumulative_weight` and
+        /// `coverage` (percentage of tips) is confirmed.
+        pub fn confirms(&self, cumulative_weight: u64, coverage: f64) -> bool {
+            match self {
+                Rule::Weight(weight) => cumulative_weight >= *weight,
+                Rule::Coverage(percentage) => coverage >= *percentage,
+                Rule::And(left, right) => {
+                    left.confirms(cumulative_weight, coverage)
+                        && right.confirms(cumulative_weight, coverage)
+                }
+                Rule::Or(left, right) => {
+                    left.confirms(cumulative_weight, coverage)
+                        || right.confirms(cumulative_weight, coverage)
+                }
+            }
+        }
+    }
+
+    impl fmt::Display for Rule {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Rule::Weight(weight) => write!(f, "weight >= {:}", weight),
+                Rule::Coverage(percentage) => write!(f, "coverage >= {:}%", percentage),
+                Rule::And(left, right) => write!(f, "{:} and {:}", left, right),
+                Rule::Or(left, right) => write!(f, "{:} or {:}", left, right),
+            }
+        }
+    }
+
+    /// Rules are `weight >= W` and `coverage >= P%` conditions combined
+    /// with `and` and `or`, `and` binds tighter.
+    impl FromStr for Rule {
+        type Err = ConfirmationError;
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            let invalid = || ConfirmationError::InvalidRule(value.to_string());
+            let condition = |text: &str| {
+                let text = text.split_whitespace().collect::<String>();
+                match text.split_once(">=") {
+                    Some(("weight", weight)) => weight.parse().map(Rule::Weight).ok(),
+                    Some(("coverage", percentage)) => percentage
+                        .trim_end_matches('%')
+                        .parse()
+                        .ok()
+                        .filter(|percentage| (0.0..=100.0).contains(percentage))
+                        .map(Rule::Coverage),
+                    _ => None,
+                }
+            };
+            let lowercase = value.to_lowercase();
+            let mut any = None;
+            for alternative in lowercase.split(" or ") {
+                let mut all = None;
+                for text in alternative.split(" and ") {
+                    let rule = condition(text).ok_or_else(invalid)?;
+                    all = Some(match all {
+                        Some(left) => Rule::And(Box::new(left), Box::new(rule)),
+                        None => rule,
+                    });
+                }
+                let rule = all.ok_or_else(invalid)?;
+                any = Some(match any {
+                    Some(left) => Rule::Or(Box::new(left), Box::new(rule)),
+                    None => rule,
+                });
+            }
+            any.ok_or_else(invalid)
+        }
+    }
+
+    /// Confirmation of every transaction of graph `graph` under `rule`,
+    /// with the rates per timestamp range. The cumulative weights and the
+    /// tip coverage take O(n²/64) time for n transactions (see
+    /// `Graph::cumulative_weights`).
+    pub fn confirmations(graph: &Graph, rule: &Rule) -> dto::Confirmations {
+        let weights = graph.cumulative_weights();
+        let coverage = graph.tip_coverage();
+        let tips = graph.tips().len().max(1) as f64;
+        let mut transactions = graph
             .nodes
             .values()
-            .map(|node| node.metrics.in_reference)
-            .sum::<u32>() as f64
-            / graph.num_nodes as f64
+            .map(|node| {
+                let cumulative_weight = weights[&node.id];
+                let coverage = coverage[&node.id] as f64 * 100.0 / tips;
+                dto::Confirmation {
+                    id: node.id,
+                    cumulative_weight,
+                    coverage,
+                    confirmed: rule.confirms(cumulative_weight, coverage),
+                }
+            })
+            .collect::<Vec<dto::Confirmation>>();
+        transactions.sort_by_key(|confirmation| confirmation.id);
+        let mut by_time_range: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+        for confirmation in transactions.iter() {
+            let range = graph.nodes[&confirmation.id].timestamp / TIMESTAMP_RANGE;
+            let (confirmed, total) = by_time_range.entry(range).or_default();
+            *confirmed += confirmation.confirmed as u64;
+            *total += 1;
+        }
+        let confirmed = transactions.iter().filter(|c| c.confirmed).count() as u64;
+        let total = transactions.len() as u64;
+        dto::Confirmations {
+            rule: rule.to_string(),
+            confirmed,
+            total,
+            rate: confirmed as f64 / total.max(1) as f64,
+            by_time_range: by_time_range
+                .into_iter()
+                .map(|(range, (confirmed, total))| (range, confirmed, total))
+                .collect(),
+            transactions,
+        }
+    }
+}
+
+/// Milestone services, they find the milestone confirming every
+/// transaction, as the periodic checkpoints of IOTA-like ledgers.
+pub mod milestones {
+    use super::dto;
+    use super::statistics::TIMESTAMP_RANGE;
+    use crate::graph::Graph;
+    use std::collections::{BTreeMap, HashMap};
+    use thiserror::Error;
+
+    /// Set of possible milestone errors.
+    #[derive(Error, Debug, PartialEq)]
+    pub enum MilestoneError {
+        #[error("unknown milestone id=`{0}`")]
+        UnknownTransaction(u32),
+        #[error("the milestone period must be positive")]
+        InvalidPeriod,
     }
 
-    // Iterate across all the nodes for setting up a ranking of timestamp.
-    // This ranking can be precalculated in the load graph function if
-    // it is necessary speedup it
-    fn range_timestamps(graph: &Graph) -> Vec<(u32, u64)> {
-        let mut range_timestamps: HashMap<u32, u64> = HashMap::new();
+    /// Milestones issued every `period` time units: the latest transaction
+    /// (greatest timestamp, ties by the greatest id) of every period with
+    /// transactions, sorted by id.
+    pub fn periodic(graph: &Graph, period: u32) -> Result<Vec<u32>, MilestoneError> {
+        if period == 0 {
+            return Err(MilestoneError::InvalidPeriod);
+        }
+        let mut latest: BTreeMap<u32, (u32, u32)> = BTreeMap::new();
         for node in graph.nodes.values() {
-            let range = node.timestamp / TIMESTAMP_RANGE;
-            let entry = range_timestamps.entry(range).or_insert(0);
-            *entry += 1;
+            let last = latest.entry(node.timestamp / period).or_default();
+            *last = (*last).max((node.timestamp, node.id));
         }
-        let mut items = range_timestamps
+        let mut ids = latest.into_values().map(|(_, id)| id).collect::<Vec<u32>>();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Confirmation of every transaction of graph `graph` by `milestones`,
+    /// issued in timestamp order (ties by id). A milestone confirms itself
+    /// and its past cone.
+    pub fn confirmations(
+        graph: &Graph,
+        milestones: &[u32],
+    ) -> Result<dto::Milestones, MilestoneError> {
+        let mut ids = milestones.to_vec();
+        ids.sort();
+        ids.dedup();
+        if let Some(id) = ids.iter().find(|id| !graph.nodes.contains_key(id)) {
+            return Err(MilestoneError::UnknownTransaction(*id));
+        }
+        ids.sort_by_key(|id| (graph.nodes[id].timestamp, *id));
+        // the past cone of a milestone contains the past cones of the
+        // transactions it confirms, so confirmed ones are not walked again
+        let mut confirming: HashMap<u32, u32> = HashMap::new();
+        let mut issued = Vec::with_capacity(ids.len());
+        let mut previous: Option<u32> = None;
+        for id in ids {
+            let timestamp = graph.nodes[&id].timestamp;
+            let mut confirmed = 0;
+            let mut pending = vec![id];
+            while let Some(ancestor) = pending.pop() {
+                if confirming.contains_key(&ancestor) {
+                    continue;
+                }
+                confirming.insert(ancestor, id);
+                confirmed += 1;
+                if let Some((left, right)) = graph.nodes[&ancestor].parents {
+                    pending.extend([left, right]);
+                }
+            }
+            issued.push(dto::Milestone {
+                id,
+                timestamp,
+                interval: previous.map(|previous| timestamp.saturating_sub(previous)),
+                confirmed,
+            });
+            previous = Some(timestamp);
+        }
+        let mut transactions = graph
+            .nodes
+            .values()
+            .map(|node| {
+                let milestone = confirming.get(&node.id).copied();
+                dto::MilestoneConfirmation {
+                    id: node.id,
+                    milestone,
+                    latency: milestone
+                        .map(|id| graph.nodes[&id].timestamp.saturating_sub(node.timestamp)),
+                }
+            })
+            .collect::<Vec<dto::MilestoneConfirmation>>();
+        transactions.sort_by_key(|confirmation| confirmation.id);
+        let latencies = transactions
             .iter()
-            .map(|(&a, &b)| (a, b))
-            .collect::<Vec<(u32, u64)>>();
-        items.sort_by_key(|&k| k);
-        items
+            .filter_map(|confirmation| confirmation.latency)
+            .collect::<Vec<u32>>();
+        let mut by_time_range: BTreeMap<u32, u64> = BTreeMap::new();
+        for latency in latencies.iter() {
+            *by_time_range.entry(latency / TIMESTAMP_RANGE).or_default() += 1;
+        }
+        let intervals = issued
+            .iter()
+            .filter_map(|milestone| milestone.interval)
+            .collect::<Vec<u32>>();
+        Ok(dto::Milestones {
+            confirmed: latencies.len() as u64,
+            unconfirmed: (transactions.len() - latencies.len()) as u64,
+            average_interval: intervals.iter().map(|i| *i as f64).sum::<f64>()
+                / intervals.len().max(1) as f64,
+            average_latency: latencies.iter().map(|l| *l as f64).sum::<f64>()
+                / latencies.len().max(1) as f64,
+            max_latency: latencies.iter().copied().max().unwrap_or_default(),
+            latencies: by_time_range.into_iter().collect(),
+            milestones: issued,
+            transactions,
+        })
     }
+}
 
-    /// Calculate statistics from graph `graph`.
-    pub fn stats(graph: &Graph) -> dto::Statistics {
-        let average_depth = average_depth(graph);
-        let average_nodes_by_depth = average_nodes_by_depth(graph);
-        let average_in_references = average_in_references(graph);
-        let range_timestamps = range_timestamps(graph);
-        let last_transaction = graph.metrics.last_transaction;
-        let most_referenced_transaction = graph.metrics.most_in_reference_transaction;
-        dto::Statistics {
-            average_depth,
-            average_nodes_by_depth,
-            average_in_references,
-            last_transaction,
-            most_referenced_transaction,
-            range_timestamps,
+/// Conflict services, they resolve sets of mutually conflicting
+/// transactions (double spends) keeping the heaviest one.
+pub mod conflicts {
+    use super::dto;
+    use crate::graph::Graph;
+    use std::collections::{BTreeSet, HashSet};
+    use thiserror::Error;
+
+    /// Set of possible conflict errors.
+    #[derive(Error, Debug, PartialEq)]
+    pub enum ConflictError {
+        #[error("unknown conflicting transaction id=`{0}`")]
+        UnknownTransaction(u32),
+        #[error("the conflict set {0:?} needs at least two transactions")]
+        SingleTransaction(Vec<u32>),
+    }
+
+    /// Resolution of the conflict `sets` of graph `graph`, one set after
+    /// the other by their lowest id. The transaction of every set with the
+    /// highest cumulative weight wins, the lowest id on ties, and the
+    /// future cones of the others are invalidated. The weights only count
+    /// the transactions not invalidated by the sets resolved before, and
+    /// the transactions already invalidated (weight 0) neither win nor
+    /// root a new subtangle.
+    pub fn resolve(graph: &Graph, sets: &[Vec<u32>]) -> Result<dto::Conflicts, ConflictError> {
+        let mut ordered = Vec::with_capacity(sets.len());
+        for set in sets.iter() {
+            let mut ids = set.clone();
+            ids.sort();
+            ids.dedup();
+            if let Some(id) = ids.iter().find(|id| !graph.nodes.contains_key(id)) {
+                return Err(ConflictError::UnknownTransaction(*id));
+            }
+            if ids.len() < 2 {
+                return Err(ConflictError::SingleTransaction(ids));
+            }
+            ordered.push(ids);
+        }
+        ordered.sort_by_key(|ids| ids[0]);
+        let mut conflicts = Vec::with_capacity(ordered.len());
+        let mut inconsistent: BTreeSet<u32> = BTreeSet::new();
+        let mut invalidated: BTreeSet<u32> = BTreeSet::new();
+        let mut subtangles = 0;
+        for ids in ordered {
+            let cones = ids
+                .iter()
+                .map(|id| {
+                    let mut cone = graph.future_cone(*id);
+                    cone.insert(*id);
+                    cone
+                })
+                .collect::<Vec<HashSet<u32>>>();
+            // transactions seeing one of the conflicting ones, those seeing
+            // two of them are inconsistent
+            let mut seen: HashSet<u32> = HashSet::new();
+            for approver in cones.iter().flatten() {
+                if !seen.insert(*approver) {
+                    inconsistent.insert(*approver);
+                }
+            }
+            let weights = ids
+                .iter()
+                .zip(cones.iter())
+                .map(|(id, cone)| {
+                    if invalidated.contains(id) {
+                        return (*id, 0);
+                    }
+                    let weight = cone.iter().filter(|id| !invalidated.contains(id)).count();
+                    (*id, weight as u64)
+                })
+                .collect::<Vec<(u32, u64)>>();
+            let winner = weights
+                .iter()
+                .filter(|(id, _)| !invalidated.contains(id))
+                .max_by_key(|&&(id, weight)| (weight, std::cmp::Reverse(id)))
+                .map(|&(id, _)| id);
+            // losers in the subtangle of a lower one are not new subtangles
+            let mut lost: HashSet<u32> = HashSet::new();
+            for (id, cone) in ids.iter().zip(cones) {
+                if Some(*id) == winner || invalidated.contains(id) || lost.contains(id) {
+                    continue;
+                }
+                lost.extend(cone.into_iter().filter(|id| !invalidated.contains(id)));
+                subtangles += 1;
+            }
+            invalidated.extend(lost.iter().copied());
+            conflicts.push(dto::Conflict {
+                transactions: weights,
+                winner,
+                invalidated: lost.len() as u64,
+            });
+        }
+        Ok(dto::Conflicts {
+            conflicts,
+            inconsistent: inconsistent.into_iter().collect(),
+            subtangles,
+            invalidated: invalidated.into_iter().collect(),
+            total: graph.nodes.len() as u64,
+        })
+    }
+}
+
+/// Inspection services for single transactions
+pub mod inspection {
+    use super::dto;
+    use crate::graph::Graph;
+    use thiserror::Error;
+
+    /// Set of possible errors inspecting transactions.
+    #[derive(Error, Debug, PartialEq)]
+    pub enum InspectionError {
+        #[error("unknown transaction id=`{0}`")]
+        UnknownTransaction(u32),
+    }
+
+    /// Details of transaction `id` in graph `graph`: parents, metrics,
+    /// approvers sorted by id and the sizes of its cones.
+    pub fn inspect(graph: &Graph, id: u32) -> Result<dto::Inspection, InspectionError> {
+        let node = graph
+            .nodes
+            .get(&id)
+            .ok_or(InspectionError::UnknownTransaction(id))?;
+        let mut approvers = graph.approvers(id).to_vec();
+        approvers.sort();
+        let cumulative_weight = graph.cumulative_weight(id);
+        Ok(dto::Inspection {
+            id,
+            parents: node.parents,
+            timestamp: node.timestamp,
+            depth: node.metrics.depth,
+            generation: node.metrics.generation,
+            in_reference: node.metrics.in_reference,
+            approvers,
+            cumulative_weight,
+            past_cone: graph.past_cone(id).len() as u64,
+            future_cone: cumulative_weight - 1,
+            tip: graph.is_tip(id),
+        })
+    }
+
+    /// Paths between transactions `from` and `to` of graph `graph`, in
+    /// either direction, listing up to `limit` of them.
+    pub fn paths(
+        graph: &Graph,
+        from: u32,
+        to: u32,
+        limit: usize,
+    ) -> Result<dto::Paths, InspectionError> {
+        for id in [from, to] {
+            if !graph.nodes.contains_key(&id) {
+                return Err(InspectionError::UnknownTransaction(id));
+            }
+        }
+        let shortest = graph.path_between(from, to);
+        let (newer, older) = match shortest.as_deref() {
+            Some([first, .., last]) => (*first, *last),
+            _ => (from, to),
+        };
+        let count = if shortest.is_some() {
+            graph.count_paths(newer, older)
+        } else {
+            0
+        };
+        Ok(dto::Paths {
+            from,
+            to,
+            shortest,
+            count,
+            paths: graph.all_paths(newer, older, limit),
+        })
+    }
+
+    /// Lowest common ancestors of transactions `a` and `b` of graph `graph`,
+    /// sorted by id.
+    pub fn common_ancestors(
+        graph: &Graph,
+        a: u32,
+        b: u32,
+    ) -> Result<Vec<dto::Ancestor>, InspectionError> {
+        for id in [a, b] {
+            if !graph.nodes.contains_key(&id) {
+                return Err(InspectionError::UnknownTransaction(id));
+            }
         }
+        Ok(graph
+            .lowest_common_ancestors(a, b)
+            .into_iter()
+            .map(|id| dto::Ancestor {
+                id,
+                depth: graph.nodes[&id].metrics.depth,
+                timestamp: graph.nodes[&id].timestamp,
+            })
+            .collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::ParentPolicy;
     use crate::graph::Graph;
     use crate::services::dto::Statistics;
     use approx::*;
@@ -119,6 +1440,25 @@ mod tests {
         assert_eq!(1, stats.most_referenced_transaction);
     }
 
+    #[test]
+    fn should_calculate_stats_same_and_distinct_parents() {
+        let stats = statistics::stats(&Graph::try_from(TEST.to_vec()).unwrap());
+        assert_eq!(3, stats.same_parent_transactions);
+        assert_eq!(2, stats.distinct_parent_transactions);
+        let stats = statistics::stats(&Graph::try_from(TEST_2.to_vec()).unwrap());
+        assert_eq!(4, stats.same_parent_transactions);
+        assert_eq!(0, stats.distinct_parent_transactions);
+    }
+
+    #[test]
+    fn should_calculate_stats_counting_duplicated_parents_once() {
+        let graph = Graph::from_nodes(TEST.to_vec(), ParentPolicy::CountOnce).unwrap();
+        let stats: Statistics = statistics::stats(&graph);
+        assert_relative_eq!(1.16, stats.average_in_references, epsilon = 0.01);
+        assert_eq!(3, stats.same_parent_transactions);
+        assert_eq!(2, stats.distinct_parent_transactions);
+    }
+
     #[test]
     fn should_calculate_stats_test_2() {
         let graph = Graph::try_from(TEST_2.to_vec()).unwrap();
@@ -131,6 +1471,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn should_calculate_stats_timestamp() {
         let graph = Graph::try_from(TEST_3.to_vec()).unwrap();
         let range_timestamps: Vec<(u32, u64)> = statistics::stats(&graph).range_timestamps;
@@ -144,4 +1485,467 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn should_calculate_stats_width() {
+        let stats = statistics::stats(&Graph::try_from(TEST.to_vec()).unwrap());
+        assert_eq!(2, stats.max_layer_width);
+        assert_eq!(2, stats.max_width);
+        assert_eq!(vec![(0, 1), (1, 2), (2, 3)], stats.transactions_by_depth);
+        assert_eq!(vec![(0, 2)], stats.width_over_time);
+
+        let stats = statistics::stats(&Graph::try_from(TEST_3.to_vec()).unwrap());
+        assert_eq!(2, stats.max_layer_width);
+        // the tips 5 to 9 approve distinct transactions of a single chain
+        assert_eq!(5, stats.max_width);
+        // the range 3 has no transactions, so it is not listed
+        assert_eq!(vec![(0, 1), (1, 2), (2, 4), (4, 5)], stats.width_over_time);
+
+        // epoch timestamps only visit the ranges with transactions
+        let epoch = [(1, 1, 1_700_000_000), (2, 2, 1_700_000_100)];
+        let stats = statistics::stats(&Graph::try_from(epoch.to_vec()).unwrap());
+        assert_eq!(
+            vec![(0, 1), (170_000_000, 1), (170_000_010, 1)],
+            stats.width_over_time
+        );
+    }
+
+    struct RootApprovers;
+
+    impl statistics::Statistic for RootApprovers {
+        fn name(&self) -> &str {
+            "root_approvers"
+        }
+        fn label(&self) -> &str {
+            "ROOT APPROVERS"
+        }
+        fn compute(&self, graph: &Graph) -> dto::Value {
+            dto::Value::Count(graph.approvers(1).len() as u64)
+        }
+    }
+
+    #[test]
+    fn should_compute_selected_statistics_from_registry() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let registry = statistics::Registry::with_builtins();
+        let selection = vec![
+            "max_layer_width".to_string(),
+            "avg_txs_per_depth".to_string(),
+        ];
+        let entries = registry.compute(&graph, &selection).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("MAX LAYER WIDTH", entries[0].label);
+        assert_eq!(dto::Value::Count(2), entries[0].value);
+        assert_eq!(dto::Value::Float(2.5), entries[1].value);
+        assert_eq!(12, registry.compute(&graph, &[]).unwrap().len());
+    }
+
+    #[test]
+    fn should_fail_computing_unknown_statistic() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let registry = statistics::Registry::with_builtins();
+        assert_eq!(
+            Err(statistics::StatisticsError::UnknownStatistic(
+                "unknown".to_string()
+            )),
+            registry.compute(&graph, &["unknown".to_string()])
+        );
+    }
+
+    #[test]
+    fn should_compute_a_registered_custom_statistic() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let mut registry = statistics::Registry::with_builtins();
+        registry.register(Box::new(RootApprovers)).unwrap();
+        assert_eq!(Some(&"root_approvers"), registry.names().last());
+        let entries = registry
+            .compute(&graph, &["root_approvers".to_string()])
+            .unwrap();
+        assert_eq!(dto::Value::Count(2), entries[0].value);
+        assert_eq!(entries, registry.compute_custom(&graph));
+        assert_eq!(
+            Err(statistics::StatisticsError::DuplicatedStatistic(
+                "root_approvers".to_string()
+            )),
+            registry.register(Box::new(RootApprovers))
+        );
+    }
+
+    struct Named(&'static str);
+
+    impl statistics::Statistic for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn label(&self) -> &str {
+            "NAMED"
+        }
+        fn compute(&self, _graph: &Graph) -> dto::Value {
+            dto::Value::Count(0)
+        }
+    }
+
+    #[test]
+    fn should_reject_reserved_and_invalid_statistic_names() {
+        use statistics::StatisticsError::*;
+        let mut registry = statistics::Registry::with_builtins();
+        for name in ["average_depth", "schema_version"] {
+            assert_eq!(
+                Err(ReservedStatistic(name.to_string())),
+                registry.register(Box::new(Named(name)))
+            );
+        }
+        for name in ["", "tx-rate", "tx.rate", "tx rate", "TxRate", "1st"] {
+            assert_eq!(
+                Err(InvalidStatisticName(name.to_string())),
+                registry.register(Box::new(Named(name)))
+            );
+        }
+        assert_eq!(Ok(()), registry.register(Box::new(Named("_tx_rate_2"))));
+    }
+
+    #[test]
+    fn should_classify_transaction_liveness() {
+        use liveness::Status::*;
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let thresholds = liveness::Thresholds {
+            max_age: 0,
+            confirmation: confirmation::Rule::Weight(3),
+        };
+        assert_eq!(
+            vec![
+                (1, Confirmed),
+                (2, Confirmed),
+                (3, Confirmed),
+                (4, Pending),
+                (5, LeftBehind),
+                (6, Pending)
+            ],
+            liveness::classify(&graph, &thresholds)
+        );
+        let mut registry = statistics::Registry::with_builtins();
+        liveness::register(&mut registry, thresholds.clone()).unwrap();
+        let covered = liveness::Thresholds {
+            max_age: 0,
+            confirmation: "coverage >= 100%".parse().unwrap(),
+        };
+        assert_eq!(
+            liveness::transactions(&graph, &covered, Confirmed),
+            confirmation::confirmations(&graph, &covered.confirmation)
+                .transactions
+                .iter()
+                .filter(|confirmation| confirmation.confirmed)
+                .map(|confirmation| confirmation.id)
+                .collect::<Vec<u32>>()
+        );
+        assert!(registry.compute_custom(&graph).is_empty());
+        let entries = registry
+            .compute(&graph, &["left_behind".to_string(), "pending".to_string()])
+            .unwrap();
+        assert_eq!(dto::Value::Transactions(vec![5]), entries[0].value);
+        assert_eq!(dto::Value::Transactions(vec![4, 6]), entries[1].value);
+        // a changed graph is classified again
+        let mut graph = graph;
+        graph.append(5, 6, 3).unwrap();
+        let statuses = ["confirmed", "pending", "left_behind"].map(String::from);
+        let entries = registry.compute(&graph, &statuses).unwrap();
+        assert_eq!(dto::Value::Transactions(vec![1, 2, 3, 4]), entries[0].value);
+        assert_eq!(dto::Value::Transactions(vec![5, 6, 7]), entries[1].value);
+        assert_eq!(dto::Value::Transactions(vec![]), entries[2].value);
+    }
+
+    #[test]
+    fn should_parse_confirmation_rules() {
+        use confirmation::Rule;
+        let rule: Rule = "weight >= 3 and coverage>=50% OR weight >= 6"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Rule::Or(
+                Box::new(Rule::And(
+                    Box::new(Rule::Weight(3)),
+                    Box::new(Rule::Coverage(50.0))
+                )),
+                Box::new(Rule::Weight(6))
+            ),
+            rule
+        );
+        assert_eq!(
+            "weight >= 3 and coverage >= 50% or weight >= 6",
+            rule.to_string()
+        );
+        assert!(rule.confirms(6, 0.0) && rule.confirms(3, 50.0) && !rule.confirms(5, 49.0));
+        for invalid in ["", "weight > 3", "coverage >= 120", "weight >= 3 and"] {
+            assert!(invalid.parse::<Rule>().is_err(), "{:}", invalid);
+        }
+    }
+
+    #[test]
+    fn should_confirm_transactions_under_a_rule() {
+        let graph = Graph::try_from(TEST_3.to_vec()).unwrap();
+        let rule = "coverage >= 50".parse().unwrap();
+        let confirmations = confirmation::confirmations(&graph, &rule);
+        // tips 5 to 9: 4 is covered by 5 and 9, 3 also by 8 and 2 also by 7
+        let confirmed = confirmations
+            .transactions
+            .iter()
+            .filter(|c| c.confirmed)
+            .map(|c| c.id)
+            .collect::<Vec<u32>>();
+        assert_eq!(vec![1, 2, 3], confirmed);
+        assert_eq!((3, 9), (confirmations.confirmed, confirmations.total));
+        assert_eq!(
+            vec![(0, 3, 4), (1, 0, 2), (2, 0, 2), (4, 0, 1)],
+            confirmations.by_time_range
+        );
+    }
+
+    #[test]
+    fn should_confirm_transactions_by_milestones() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        assert_eq!(Ok(vec![4, 6]), milestones::periodic(&graph, 2));
+        let milestones = milestones::confirmations(&graph, &[6, 3]).unwrap();
+        assert_eq!(
+            vec![(3, 0, None, 3), (6, 3, Some(3), 2)],
+            milestones
+                .milestones
+                .iter()
+                .map(|m| (m.id, m.timestamp, m.interval, m.confirmed))
+                .collect::<Vec<(u32, u32, Option<u32>, u64)>>()
+        );
+        // 5 is not in the past cone of 6, 4 waits from timestamp 1 to 3
+        assert_eq!(
+            vec![
+                (Some(3), Some(0)),
+                (Some(3), Some(0)),
+                (Some(3), Some(0)),
+                (Some(6), Some(2)),
+                (None, None),
+                (Some(6), Some(0))
+            ],
+            milestones
+                .transactions
+                .iter()
+                .map(|c| (c.milestone, c.latency))
+                .collect::<Vec<(Option<u32>, Option<u32>)>>()
+        );
+        assert_eq!(
+            (5, 1, 2),
+            (
+                milestones.confirmed,
+                milestones.unconfirmed,
+                milestones.max_latency
+            )
+        );
+        assert_relative_eq!(0.4, milestones.average_latency);
+        // 7 is issued before 6, at an earlier timestamp
+        let unordered = Graph::try_from(TEST_3.to_vec()).unwrap();
+        // 6 (22) is after 7 (14) in [0, 25) and 8 (41) after 9 (28) in [25, 50)
+        assert_eq!(Ok(vec![6, 8]), milestones::periodic(&unordered, 25));
+        let milestones = milestones::confirmations(&unordered, &[6, 7]).unwrap();
+        assert_eq!(
+            vec![(7, 14, None, 3), (6, 22, Some(8), 1)],
+            milestones
+                .milestones
+                .iter()
+                .map(|m| (m.id, m.timestamp, m.interval, m.confirmed))
+                .collect::<Vec<(u32, u32, Option<u32>, u64)>>()
+        );
+        assert_eq!(Some(14), milestones.transactions[0].latency);
+        assert_eq!(
+            Err(milestones::MilestoneError::UnknownTransaction(9)),
+            milestones::confirmations(&graph, &[9])
+        );
+    }
+
+    #[test]
+    fn should_resolve_conflicts_by_cumulative_weight() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let summary = |resolution: &dto::Conflicts| {
+            resolution
+                .conflicts
+                .iter()
+                .map(|c| (c.transactions.clone(), c.winner, c.invalidated))
+                .collect::<Vec<(Vec<(u32, u64)>, Option<u32>, u64)>>()
+        };
+        // 5 is already invalidated with the future cone of 3 and 6 does not
+        // count in the weight of 4
+        let resolution = conflicts::resolve(&graph, &[vec![5, 4], vec![2, 3]]).unwrap();
+        assert_eq!(
+            vec![
+                (vec![(2, 5), (3, 3)], Some(2), 3),
+                (vec![(4, 1), (5, 0)], Some(4), 0)
+            ],
+            summary(&resolution)
+        );
+        // 3 approves 2, so 3 and its future cone see both of them
+        assert_eq!(vec![3, 5, 6], resolution.inconsistent);
+        assert_eq!(vec![3, 5, 6], resolution.invalidated);
+        assert_eq!((1, 6), (resolution.subtangles, resolution.total));
+        let resolution = conflicts::resolve(&graph, &[vec![2, 3], vec![5, 6]]).unwrap();
+        assert_eq!(
+            (None, 0),
+            (
+                resolution.conflicts[1].winner,
+                resolution.conflicts[1].invalidated
+            )
+        );
+        assert_eq!(1, resolution.subtangles);
+        assert_eq!(
+            Err(conflicts::ConflictError::SingleTransaction(vec![4])),
+            conflicts::resolve(&graph, &[vec![4, 4]])
+        );
+    }
+
+    #[test]
+    fn should_resolve_interacting_conflicts_in_order() {
+        // 5 approves 3 and 8, 9 approve 4
+        let graph = Graph::try_from(vec![
+            (1, 1, 0),
+            (1, 1, 0),
+            (1, 1, 0),
+            (3, 3, 1),
+            (3, 3, 1),
+            (2, 2, 1),
+            (4, 4, 1),
+            (4, 4, 1),
+        ])
+        .unwrap();
+        let resolution = conflicts::resolve(&graph, &[vec![4, 3], vec![5, 2]]).unwrap();
+        // without 5 the weight of 3 drops from 3 to 2 and 4 wins
+        assert_eq!(
+            vec![
+                (vec![(2, 2), (5, 1)], Some(2), 1),
+                (vec![(3, 2), (4, 3)], Some(4), 2)
+            ],
+            resolution
+                .conflicts
+                .iter()
+                .map(|c| (c.transactions.clone(), c.winner, c.invalidated))
+                .collect::<Vec<(Vec<(u32, u64)>, Option<u32>, u64)>>()
+        );
+        assert_eq!(vec![3, 5, 6], resolution.invalidated);
+        assert_eq!(2, resolution.subtangles);
+    }
+
+    #[test]
+    fn should_calculate_same_stats_in_parallel() {
+        let nodes = (0..500_u32)
+            .map(|index| (index / 2 + 1, index / 3 + 1, (index * 7) % 130))
+            .collect::<Vec<(u32, u32, u32)>>();
+        for values in [TEST.to_vec(), TEST_3.to_vec(), nodes] {
+            let graph = Graph::try_from(values).unwrap();
+            let sequential = statistics::stats(&graph);
+            for threads in 2..=5 {
+                assert_eq!(sequential, statistics::stats_parallel(&graph, threads));
+            }
+        }
+    }
+
+    #[test]
+    fn should_only_feed_the_selected_accumulators() {
+        let graph = Graph::try_from(TEST_3.to_vec()).unwrap();
+        let all = statistics::stats(&graph);
+        let timestamps = statistics::Accumulated {
+            timestamps: true,
+            ..statistics::Accumulated::default()
+        };
+        let selected = statistics::stats_with(&graph, timestamps, 2);
+        assert_eq!(all.range_timestamps, selected.range_timestamps);
+        assert!(selected.transactions_by_depth.is_empty() && selected.width_over_time.is_empty());
+        assert_eq!(0, selected.max_layer_width);
+        assert_eq!(0, selected.max_width);
+        let entries = statistics::Registry::with_builtins()
+            .compute(&graph, &["avg_depth".to_string()])
+            .unwrap();
+        assert_eq!(dto::Value::Float(all.average_depth), entries[0].value);
+    }
+
+    #[test]
+    fn should_calculate_in_reference_distribution_and_top_referenced() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        assert_eq!(
+            vec![(0, 2), (1, 1), (3, 3)],
+            statistics::in_reference_distribution(&graph)
+        );
+        assert_eq!(vec![(1, 3), (2, 3)], statistics::top_referenced(&graph, 2));
+    }
+
+    #[test]
+    fn should_inspect_a_transaction() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let inspection = inspection::inspect(&graph, 3).unwrap();
+        assert_eq!(
+            dto::Inspection {
+                id: 3,
+                parents: Some((1, 2)),
+                timestamp: 0,
+                depth: 1,
+                generation: 2,
+                in_reference: 3,
+                approvers: vec![5, 6],
+                cumulative_weight: 3,
+                past_cone: 2,
+                future_cone: 2,
+                tip: false,
+            },
+            inspection
+        );
+        assert!(inspection::inspect(&graph, 6).unwrap().tip);
+        assert_eq!(
+            Err(inspection::InspectionError::UnknownTransaction(9)),
+            inspection::inspect(&graph, 9)
+        );
+    }
+
+    #[test]
+    fn should_find_paths_in_either_direction() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let paths = inspection::paths(&graph, 2, 6, 10).unwrap();
+        assert_eq!(Some(vec![6, 3, 2]), paths.shortest);
+        assert_eq!(2, paths.count);
+        assert_eq!(vec![vec![6, 3, 2], vec![6, 4, 2]], paths.paths);
+        let unrelated = inspection::paths(&graph, 5, 6, 10).unwrap();
+        assert_eq!((None, 0), (unrelated.shortest, unrelated.count));
+        assert!(unrelated.paths.is_empty());
+        assert_eq!(
+            Err(inspection::InspectionError::UnknownTransaction(9)),
+            inspection::paths(&graph, 1, 9, 10)
+        );
+    }
+
+    #[test]
+    fn should_describe_common_ancestors() {
+        let graph = Graph::try_from(TEST_3.to_vec()).unwrap();
+        assert_eq!(
+            Ok(vec![dto::Ancestor {
+                id: 2,
+                depth: 1,
+                timestamp: 0,
+            }]),
+            inspection::common_ancestors(&graph, 5, 7)
+        );
+        assert_eq!(
+            Err(inspection::InspectionError::UnknownTransaction(0)),
+            inspection::common_ancestors(&graph, 0, 7)
+        );
+    }
+
+    #[test]
+    fn should_calculate_stats_over_matching_transactions() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let stats = statistics::stats_matching(&graph, |node| node.metrics.depth == 2);
+        assert_eq!(2.0, stats.average_depth);
+        assert_eq!(vec![(2, 3)], stats.transactions_by_depth);
+        assert_eq!(2, stats.max_width);
+        assert_eq!(6, stats.last_transaction);
+        assert_eq!(4, stats.most_referenced_transaction);
+        assert_eq!(2, stats.same_parent_transactions);
+        let all = statistics::stats_matching(&graph, |_| true);
+        assert_eq!(statistics::stats(&graph).average_depth, all.average_depth);
+        assert_eq!(
+            0,
+            statistics::stats_matching(&graph, |_| false).last_transaction
+        );
+    }
 }
@@ -0,0 +1,305 @@
+// Simulation module. It runs tip selection random walks over a loaded
+// graph, choosing approvers uniformly or weighted by their cumulative
+// weight (MCMC), with a seeded generator so every run can be reproduced.
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::graph::Graph;
+
+/// Set of possible simulation errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum SimulationError {
+    #[error("no transaction at depth `{0}`")]
+    UnknownDepth(u32),
+    #[error("alpha must be a finite non negative number, found `{0}`")]
+    InvalidAlpha(f64),
+}
+
+/// Deterministic pseudo random generator (SplitMix64), the same `seed`
+/// always produces the same sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform number in `[0, n)`, `n` must be positive.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+/// Tip selection strategies. `Uniform` walks to any approver with the same
+/// probability, `Mcmc` to approver `y` of `x` with a probability
+/// proportional to `exp(-alpha * (H(x) - H(y)))`, `H` the cumulative weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    Uniform,
+    Mcmc { alpha: f64 },
+}
+
+/// Transaction where the walks start: the root or one chosen uniformly
+/// among the transactions at a depth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Start {
+    Root,
+    Depth(u32),
+}
+
+/// Simulation settings: the `strategy`, the `start` of the `walks` and the
+/// `seed` of the generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationOptions {
+    pub strategy: Strategy,
+    pub start: Start,
+    pub walks: u64,
+    pub seed: u64,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        SimulationOptions {
+            strategy: Strategy::Uniform,
+            start: Start::Root,
+            walks: 1000,
+            seed: 0,
+        }
+    }
+}
+
+/// Simulation results: the times every tip was `selected` sorted by id, the
+/// `orphan_rate` (share of tips never selected), the `average_length` of
+/// the walks and the `walk_lengths` (references followed, number of walks)
+/// sorted by length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub walks: u64,
+    pub selected: Vec<(u32, u64)>,
+    pub orphan_rate: f64,
+    pub average_length: f64,
+    pub walk_lengths: Vec<(u32, u64)>,
+}
+
+/// Next transaction of a walk among the `approvers` of the current one, of
+/// cumulative weight `from_weight`. `weight` gives the cumulative weights
+/// of the approvers for MCMC.
+pub(crate) fn step(
+    approvers: &[u32],
+    from_weight: u64,
+    weight: impl Fn(u32) -> u64,
+    strategy: Strategy,
+    rng: &mut Rng,
+) -> u32 {
+    let alpha = match strategy {
+        Strategy::Mcmc { alpha } if alpha > 0.0 => alpha,
+        _ => return approvers[rng.below(approvers.len() as u64) as usize],
+    };
+    let weights = approvers
+        .iter()
+        .map(|id| (-alpha * (from_weight as f64 - weight(*id) as f64)).exp())
+        .collect::<Vec<f64>>();
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 || !total.is_finite() {
+        // every probability underflowed: the heaviest approver wins
+        return *approvers
+            .iter()
+            .max_by_key(|id| (weight(**id), std::cmp::Reverse(**id)))
+            .expect("walks only step from transactions with approvers");
+    }
+    let mut target = rng.next_f64() * total;
+    for (id, weight) in approvers.iter().zip(weights.iter()) {
+        if target < *weight {
+            return *id;
+        }
+        target -= weight;
+    }
+    *approvers
+        .last()
+        .expect("walks only step from transactions with approvers")
+}
+
+/// Run `options.walks` tip selection walks over graph `graph`.
+pub fn simulate(graph: &Graph, options: &SimulationOptions) -> Result<Simulation, SimulationError> {
+    if let Strategy::Mcmc { alpha } = options.strategy {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(SimulationError::InvalidAlpha(alpha));
+        }
+    }
+    let starts = match options.start {
+        Start::Root => vec![1],
+        Start::Depth(depth) => {
+            let mut starts = graph
+                .nodes
+                .values()
+                .filter(|node| node.metrics.depth == depth)
+                .map(|node| node.id)
+                .collect::<Vec<u32>>();
+            if starts.is_empty() {
+                return Err(SimulationError::UnknownDepth(depth));
+            }
+            starts.sort();
+            starts
+        }
+    };
+    let weights = match options.strategy {
+        Strategy::Mcmc { .. } => graph.cumulative_weights(),
+        Strategy::Uniform => HashMap::new(),
+    };
+    let weight = |id: u32| weights.get(&id).copied().unwrap_or(1);
+    let mut approvers: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut rng = Rng::new(options.seed);
+    let mut selected: BTreeMap<u32, u64> = graph.tips().into_iter().map(|id| (id, 0)).collect();
+    let mut walk_lengths: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut total_length = 0u64;
+    for _ in 0..options.walks {
+        let mut id = starts[rng.below(starts.len() as u64) as usize];
+        let mut length = 0;
+        while !graph.is_tip(id) {
+            let candidates = approvers.entry(id).or_insert_with(|| {
+                let mut candidates = graph.approvers(id).to_vec();
+                candidates.sort();
+                candidates
+            });
+            id = step(candidates, weight(id), weight, options.strategy, &mut rng);
+            length += 1;
+        }
+        *selected.entry(id).or_default() += 1;
+        *walk_lengths.entry(length).or_default() += 1;
+        total_length += length as u64;
+    }
+    let orphans = selected.values().filter(|count| **count == 0).count();
+    Ok(Simulation {
+        walks: options.walks,
+        orphan_rate: orphans as f64 / selected.len().max(1) as f64,
+        average_length: total_length as f64 / options.walks.max(1) as f64,
+        selected: selected.into_iter().collect(),
+        walk_lengths: walk_lengths.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    #[test]
+    fn should_generate_reproducible_numbers() {
+        let mut rng = Rng::new(7);
+        let first = (0..4).map(|_| rng.next_u64()).collect::<Vec<u64>>();
+        let mut again = Rng::new(7);
+        assert_eq!(
+            first,
+            (0..4).map(|_| again.next_u64()).collect::<Vec<u64>>()
+        );
+        assert_ne!(first[0], Rng::new(8).next_u64());
+        assert!((0..1000).all(|_| rng.below(3) < 3 && rng.next_f64() < 1.0));
+    }
+
+    #[test]
+    fn should_simulate_walks_to_tips() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let options = SimulationOptions {
+            walks: 200,
+            seed: 3,
+            ..SimulationOptions::default()
+        };
+        let simulation = simulate(&graph, &options).unwrap();
+        assert_eq!(simulation, simulate(&graph, &options).unwrap());
+        assert_eq!(
+            vec![5, 6],
+            simulation
+                .selected
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<u32>>()
+        );
+        assert_eq!(
+            200,
+            simulation
+                .selected
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<u64>()
+        );
+        assert_eq!(0.0, simulation.orphan_rate);
+        assert_eq!(
+            vec![2, 3],
+            simulation
+                .walk_lengths
+                .iter()
+                .map(|(length, _)| *length)
+                .collect::<Vec<u32>>()
+        );
+        let from_depth = simulate(
+            &graph,
+            &SimulationOptions {
+                start: Start::Depth(2),
+                ..options.clone()
+            },
+        )
+        .unwrap();
+        // 4, 5 and 6 are at depth 2, only 4 is not a tip
+        assert_eq!(
+            vec![0, 1],
+            from_depth
+                .walk_lengths
+                .iter()
+                .map(|(length, _)| *length)
+                .collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    fn should_follow_the_heaviest_approvers_with_high_alpha() {
+        // 3 is approved by 4 and 5 (cumulative weights 1 and 2), 5 by 6
+        let graph =
+            Graph::try_from(vec![(1, 1, 0), (2, 2, 1), (3, 3, 2), (3, 3, 2), (5, 5, 3)]).unwrap();
+        let options = SimulationOptions {
+            strategy: Strategy::Mcmc { alpha: 50.0 },
+            walks: 100,
+            ..SimulationOptions::default()
+        };
+        let simulation = simulate(&graph, &options).unwrap();
+        assert_eq!(vec![(4, 0), (6, 100)], simulation.selected);
+        assert_eq!(0.5, simulation.orphan_rate);
+        assert_eq!(
+            Err(SimulationError::UnknownDepth(9)),
+            simulate(
+                &graph,
+                &SimulationOptions {
+                    start: Start::Depth(9),
+                    ..options.clone()
+                }
+            )
+        );
+        assert_eq!(
+            Err(SimulationError::InvalidAlpha(-1.0)),
+            simulate(
+                &graph,
+                &SimulationOptions {
+                    strategy: Strategy::Mcmc { alpha: -1.0 },
+                    ..options
+                }
+            )
+        );
+    }
+}
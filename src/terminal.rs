@@ -0,0 +1,278 @@
+// Terminal module. It renders histograms as bar charts and small DAGs as
+// layered text for quick inspections in a terminal, respecting its width
+// and disabling colors when the output is not a TTY.
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::io::IsTerminal;
+
+use thiserror::Error;
+
+use crate::domain::Transaction;
+use crate::graph::Graph;
+
+/// Width used when the terminal width is unknown.
+pub const DEFAULT_WIDTH: usize = 80;
+/// Maximum number of transactions rendered as a DAG.
+pub const MAX_DAG_NODES: usize = 200;
+
+const BAR_COLOR: &str = "\x1b[36m";
+const TIP_COLOR: &str = "\x1b[32m";
+const FOCUS_COLOR: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Set of possible terminal rendering errors.
+#[derive(Error, Debug, PartialEq)]
+pub enum TerminalError {
+    #[error("unknown transaction id=`{0}`")]
+    UnknownTransaction(u32),
+    #[error("too many transactions to render `{0}`, select a window around one")]
+    TooManyTransactions(usize),
+}
+
+/// Rendering options: terminal `width` in columns, ANSI `color` and
+/// `unicode` characters (ASCII otherwise).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalOptions {
+    pub width: usize,
+    pub color: bool,
+    pub unicode: bool,
+}
+
+impl TerminalOptions {
+    /// Options for the current standard output: the width is read from
+    /// `COLUMNS` (`DEFAULT_WIDTH` when it is not set) and colors are enabled
+    /// only in a TTY without `NO_COLOR`.
+    pub fn detect() -> Self {
+        let terminal = std::io::stdout().is_terminal();
+        let width = env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .filter(|&columns: &usize| columns > 0)
+            .unwrap_or(DEFAULT_WIDTH);
+        let color = terminal && env::var_os("NO_COLOR").is_none();
+        TerminalOptions {
+            width,
+            color,
+            unicode: true,
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{:}{:}{:}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// Bar of `length` columns for `count` of `max`, with eighths of column
+// precision in unicode.
+fn bar(count: u64, max: u64, length: usize, unicode: bool) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    let eighths = (count as u128 * length as u128 * 8 / max as u128) as usize;
+    if !unicode {
+        return "#".repeat(eighths / 8);
+    }
+    let mut output = "█".repeat(eighths / 8);
+    let remainder = eighths % 8;
+    if remainder > 0 {
+        output.push(EIGHTHS[remainder]);
+    }
+    output
+}
+
+/// Horizontal bar chart of `bars` (label, count) titled `title`, scaled to
+/// the terminal width.
+pub fn bar_chart(title: &str, bars: &[(String, u64)], options: &TerminalOptions) -> String {
+    let label_width = bars
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let count_width = bars
+        .iter()
+        .map(|(_, count)| count.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let length = options
+        .width
+        .saturating_sub(label_width + count_width + 3)
+        .max(1);
+    let max = bars.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let separator = if options.unicode { '│' } else { '|' };
+    let mut output = format!("> {:}\n", title);
+    for (label, count) in bars.iter() {
+        let bar = bar(*count, max, length, options.unicode);
+        output += format!(
+            "{:>label_width$} {:}{:} {:}\n",
+            label,
+            separator,
+            options.paint(BAR_COLOR, &bar),
+            count,
+            label_width = label_width
+        )
+        .as_str();
+    }
+    output
+}
+
+// Transactions at most `radius` references (in any direction) away from `id`.
+fn window(graph: &Graph, id: u32, radius: u32) -> HashSet<u32> {
+    let mut selected = HashSet::from([id]);
+    let mut frontier = vec![id];
+    for _ in 0..radius {
+        let mut next = Vec::new();
+        for node_id in frontier {
+            let parents = graph.nodes[&node_id]
+                .parents
+                .map_or(Vec::new(), |(left, right)| vec![left, right]);
+            for neighbour in parents
+                .into_iter()
+                .chain(graph.approvers(node_id).iter().copied())
+            {
+                if selected.insert(neighbour) {
+                    next.push(neighbour);
+                }
+            }
+        }
+        frontier = next;
+    }
+    selected
+}
+
+/// Layered text rendering of graph `graph`, one line per depth with every
+/// transaction and its parents. With `around` (id, radius) only the
+/// transactions in a window around `id` are rendered. Tips are marked with
+/// `*` and the `around` transaction with brackets.
+pub fn dag(
+    graph: &Graph,
+    around: Option<(u32, u32)>,
+    options: &TerminalOptions,
+) -> Result<String, TerminalError> {
+    let selected = match around {
+        Some((id, _)) if !graph.nodes.contains_key(&id) => {
+            return Err(TerminalError::UnknownTransaction(id))
+        }
+        Some((id, radius)) => window(graph, id, radius),
+        None => graph.nodes.keys().copied().collect(),
+    };
+    if selected.len() > MAX_DAG_NODES {
+        return Err(TerminalError::TooManyTransactions(selected.len()));
+    }
+    let mut layers: BTreeMap<u32, Vec<&Transaction>> = BTreeMap::new();
+    for id in selected.iter() {
+        let node = &graph.nodes[id];
+        layers.entry(node.metrics.depth).or_default().push(node);
+    }
+    let (arrow, separator) = if options.unicode {
+        ("←", '│')
+    } else {
+        ("<-", '|')
+    };
+    let depth_width = layers
+        .keys()
+        .last()
+        .map_or(1, |depth| depth.to_string().len());
+    let mut output = String::new();
+    for (depth, nodes) in layers.iter_mut() {
+        nodes.sort_by_key(|node| node.id);
+        let prefix = format!(
+            "depth {:>depth_width$} {:} ",
+            depth,
+            separator,
+            depth_width = depth_width
+        );
+        let indent = " ".repeat(prefix.chars().count());
+        let mut line = prefix;
+        let mut line_width = line.chars().count();
+        for node in nodes.iter() {
+            let mut name = node.id.to_string();
+            let mut color = None;
+            if graph.is_tip(node.id) {
+                name += "*";
+                color = Some(TIP_COLOR);
+            }
+            if around.map(|(id, _)| id) == Some(node.id) {
+                name = format!("[{:}]", name);
+                color = Some(FOCUS_COLOR);
+            }
+            let parents = node
+                .parents
+                .map(|(left, right)| format!("{:}({:},{:})", arrow, left, right))
+                .unwrap_or_default();
+            let item_width = name.chars().count() + parents.chars().count() + 1;
+            if line_width + item_width > options.width && line_width > indent.len() {
+                output += line.trim_end();
+                output += "\n";
+                line = indent.clone();
+                line_width = indent.len();
+            }
+            let name = match color {
+                Some(color) => options.paint(color, &name),
+                None => name,
+            };
+            line += format!("{:}{:} ", name, parents).as_str();
+            line_width += item_width;
+        }
+        output += line.trim_end();
+        output += "\n";
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: [(u32, u32, u32); 5] = [(1, 1, 0), (1, 2, 0), (2, 2, 1), (3, 3, 2), (3, 4, 3)];
+
+    fn plain(width: usize) -> TerminalOptions {
+        TerminalOptions {
+            width,
+            color: false,
+            unicode: true,
+        }
+    }
+
+    #[test]
+    fn should_render_bar_chart_scaled_to_width() {
+        let bars = vec![("0:10".to_string(), 6), ("10:20".to_string(), 3)];
+        let output = bar_chart("TIMESTAMPS", &bars, &plain(20));
+        assert_eq!(
+            "> TIMESTAMPS\n 0:10 │███████████ 6\n10:20 │█████▌ 3\n",
+            output
+        );
+        let ascii = TerminalOptions {
+            unicode: false,
+            ..plain(20)
+        };
+        assert!(bar_chart("TIMESTAMPS", &bars, &ascii).contains("10:20 |##### 3\n"));
+    }
+
+    #[test]
+    fn should_render_dag_layers() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = dag(&graph, None, &plain(80)).unwrap();
+        assert_eq!(
+            "depth 0 │ 1\ndepth 1 │ 2←(1,1) 3←(1,2)\ndepth 2 │ 4←(2,2) 5*←(3,3) 6*←(3,4)\n",
+            output
+        );
+        let wrapped = dag(&graph, None, &plain(28)).unwrap();
+        assert!(wrapped.contains("depth 2 │ 4←(2,2) 5*←(3,3)\n          6*←(3,4)\n"));
+    }
+
+    #[test]
+    fn should_render_dag_window_around_transaction() {
+        let graph = Graph::try_from(TEST.to_vec()).unwrap();
+        let output = dag(&graph, Some((5, 1)), &plain(80)).unwrap();
+        assert_eq!("depth 1 │ 3←(1,2)\ndepth 2 │ [5*]←(3,3)\n", output);
+        assert_eq!(
+            Err(TerminalError::UnknownTransaction(9)),
+            dag(&graph, Some((9, 1)), &plain(80))
+        );
+    }
+}